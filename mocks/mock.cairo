@@ -0,0 +1,28 @@
+%lang starknet
+from starkware.cairo.common.alloc import alloc
+from starkware.cairo.common.cairo_builtins import HashBuiltin
+from starkware.starknet.common.syscalls import storage_read
+
+// Every call to the mock returns the values of its selector, written by the fuzzer before each execution:
+// the number of values is stored at the key `selector` and the values at the keys `selector + 1 + index`
+@external
+@raw_input
+@raw_output
+func __default__{syscall_ptr: felt*, pedersen_ptr: HashBuiltin*, range_check_ptr}(
+    selector: felt, calldata_size: felt, calldata: felt*
+) -> (retdata_size: felt, retdata: felt*) {
+    alloc_locals;
+    let (local size) = storage_read(address=selector);
+    let (local retdata: felt*) = alloc();
+    read_response(selector, 0, size, retdata);
+    return (retdata_size=size, retdata=retdata);
+}
+
+func read_response{syscall_ptr: felt*}(selector: felt, index: felt, size: felt, retdata: felt*) {
+    if (index == size) {
+        return ();
+    }
+    let (value) = storage_read(address=selector + 1 + index);
+    assert retdata[index] = value;
+    return read_response(selector, index + 1, size, retdata);
+}
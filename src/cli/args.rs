@@ -18,6 +18,14 @@ pub struct Opt {
     )]
     pub contract: String,
 
+    #[arg(
+        long,
+        help = "Set the path of the JSON artifact of the other version of the contract (differential fuzzing)",
+        name = "DIFFCONTRACT",
+        default_value = ""
+    )]
+    pub diffcontract: String,
+
     #[arg(
         long,
         help = "Set the function to fuzz",
@@ -123,4 +131,60 @@ pub struct Opt {
 
     #[arg(long, help = "Iteration Number", name = "ITER", default_value = "-1")]
     pub iter: i64,
+
+    #[arg(
+        long,
+        help = "Command of the reference implementation to compare the return values with",
+        name = "REFERENCE",
+        default_value = ""
+    )]
+    pub reference: String,
+
+    #[arg(
+        long,
+        help = "Number of calls of the sequences executed with a model",
+        name = "SEQUENCE_LENGTH",
+        default_value = "10"
+    )]
+    pub sequence_length: usize,
+
+    #[arg(
+        long,
+        help = "Check the built-in ERC20 properties on sequences of token calls",
+        name = "ERC20",
+        default_value = "false"
+    )]
+    pub erc20: bool,
+
+    #[arg(
+        long,
+        help = "Detect the state-changing functions callable by anyone once an owner is set",
+        name = "ACCESS_CONTROL",
+        default_value = "false"
+    )]
+    pub access_control: bool,
+
+    #[arg(
+        long,
+        help = "Fuzz the execution context (caller, contract address, block, sequencer, nonce, version, signature)",
+        name = "CONTEXT",
+        default_value = "false"
+    )]
+    pub context: bool,
+
+    #[arg(
+        long,
+        help = "Path to the JSON file with the initial storage of the contracts",
+        name = "STATE",
+        default_value = ""
+    )]
+    pub state: String,
+
+    #[arg(
+        long,
+        help = "Path to the JSON manifest of the other contracts to deploy",
+        name = "MANIFEST",
+        default_value = ""
+    )]
+    pub manifest: String,
 }
@@ -2,11 +2,29 @@ use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fs;
 
+/// Function of a mock contract
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct MockFunction {
+    pub name: String,
+    /// Number of values returned by the function
+    pub outputs: usize,
+}
+
+/// Mock contract whose return values are fuzzed
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct MockConfig {
+    pub address: String,
+    pub functions: Vec<MockFunction>,
+}
+
 /// Config struct to use instead of command line
 #[derive(Deserialize, Serialize, Clone, Default)]
 pub struct Config {
     pub workspace: String,
     pub contract_file: String,
+    /// Other version of the contract used for differential fuzzing
+    #[serde(default)]
+    pub diff_contract_file: String,
     pub function_name: String,
     pub input_file: String,
     pub crash_file: String,
@@ -21,6 +39,39 @@ pub struct Config {
     pub minimizer: bool,
     pub proptesting: bool,
     pub iter: i64,
+    /// Command of the reference implementation compared with the function
+    #[serde(default)]
+    pub reference_command: String,
+    /// Error messages of the reverts that are not reported as crashes
+    #[serde(default)]
+    pub expected_reverts: Vec<String>,
+    /// Number of calls of the sequences executed with a model
+    #[serde(default)]
+    pub sequence_length: usize,
+    /// Check the built-in ERC20 properties
+    #[serde(default)]
+    pub erc20: bool,
+    /// Detect the state-changing functions callable by anyone
+    #[serde(default)]
+    pub access_control: bool,
+    /// Functions expected to be callable by anyone (access-control detector)
+    #[serde(default)]
+    pub public_functions: Vec<String>,
+    /// Take the execution context of the calls from the fuzz input
+    #[serde(default)]
+    pub fuzz_context: bool,
+    /// JSON file with the initial storage of the contracts
+    #[serde(default)]
+    pub state_file: String,
+    /// JSON file with the other contracts to deploy
+    #[serde(default)]
+    pub manifest_file: String,
+    /// Mock contracts whose return values are taken from the fuzz input
+    #[serde(default)]
+    pub mocks: Vec<MockConfig>,
+    /// Artifact of the mock contract
+    #[serde(default)]
+    pub mock_artifact: String,
 }
 
 impl Config {
@@ -1,16 +1,21 @@
-use crate::mutator::mutator::{EmptyDatabase, Mutator};
-use crate::runner::runner::Runner;
-use cairo_rs::types::program::Program;
+use crate::mutator::mutator::Mutator;
+use crate::runner::runner::{format_felts, Runner};
 use felt::Felt252;
 use std::sync::{Arc, Mutex};
 
-use super::{corpus_crash::CrashFile, corpus_input::InputFile};
-//use super::dict::Dict;
+use super::dict::Dict;
+use super::reference::Reference;
+use super::revert::{is_assume, is_expected_revert};
 use super::stats::*;
+use super::{
+    corpus_crash::{CrashFile, CrashRecord},
+    corpus_input::InputFile,
+    oracle::{check_oracles, Oracle},
+};
 
 use crate::custom_rand::rng::Rng;
 use crate::json::json_parser::Function;
-use crate::runner::cairo_runner;
+use crate::runner::cairo_runner::RunnerCairo;
 use thiserror::Error;
 
 #[derive(Debug, PartialEq, Error)]
@@ -21,37 +26,46 @@ pub enum CairoworkerError {
 pub struct CairoWorker {
     stats: Arc<Mutex<Statistics>>,
     worker_id: i32,
-    program: Program,
+    cairo_runner: RunnerCairo,
     function: Function,
     seed: u64,
     input_file: Arc<Mutex<InputFile>>,
     crash_file: Arc<Mutex<CrashFile>>,
     iter: i64,
-    //dict: Dict,
+    expected_reverts: Vec<String>,
+    reference_command: String,
+    oracles: Vec<Arc<dyn Oracle>>,
+    dict: Dict,
 }
 
 impl CairoWorker {
     pub fn new(
         stats: Arc<Mutex<Statistics>>,
         worker_id: i32,
-        program: Program,
+        cairo_runner: RunnerCairo,
         function: Function,
         seed: u64,
         input_file: Arc<Mutex<InputFile>>,
         crash_file: Arc<Mutex<CrashFile>>,
         iter: i64,
-        //dict: Dict,
+        expected_reverts: Vec<String>,
+        reference_command: String,
+        oracles: Vec<Arc<dyn Oracle>>,
+        dict: Dict,
     ) -> Self {
         CairoWorker {
             stats,
             worker_id,
-            program,
+            cairo_runner,
             function,
             seed: seed,
             input_file,
             crash_file,
             iter,
-            //dict,
+            expected_reverts,
+            reference_command,
+            oracles,
+            dict,
         }
     }
 
@@ -62,11 +76,16 @@ impl CairoWorker {
         // to prevent duplication of efforts
         let rng = Rng::seeded(self.seed);
 
+        // Start the reference implementation if provided
+        let mut reference = match self.reference_command.is_empty() {
+            true => None,
+            false => Some(Reference::new(&self.reference_command)),
+        };
+
         // Create a mutator
         let mut mutator = Mutator::new()
             .seed(self.seed)
             .max_input_size(self.function.num_args as usize);
-        let cairo_runner = cairo_runner::RunnerCairo::new(&self.program);
         'next_case: loop {
             // clear previous data
             mutator.input.clear();
@@ -84,8 +103,7 @@ impl CairoWorker {
             }
 
             // Corrupt it with 4 mutation passes
-            //if self.dict.inputs.is_empty() {
-            mutator.mutate(4, &EmptyDatabase);
+            mutator.mutate(4, &local_stats, &self.dict);
 
             // not the good size, drop this input
             if mutator.input.len() != self.function.num_args as usize {
@@ -101,12 +119,19 @@ impl CairoWorker {
             let fuzz_input = Arc::new(mutator.input.clone());
             //println!("Inputs =>>> {:?}", &mutator.input);
             // run the cairo vm
-            match cairo_runner
+            let mut result = self
+                .cairo_runner
                 .clone()
-                .runner(&self.function.name, &mutator.input)
-            {
-                Ok(traces) => {
-                    let vec_trace: Vec<(u32, u32)> = traces.expect("Could not get traces");
+                .runner(&self.function.name, &mutator.input);
+            // compare the return values with the reference implementation
+            if let Some(reference) = &mut reference {
+                result = reference.check(&self.function.name, &mutator.input, result);
+            }
+            // check the execution with the oracles
+            result = check_oracles(&self.oracles, &mutator.input, result);
+            match result {
+                Ok(output) => {
+                    let vec_trace = output.expect("Could not get traces").trace;
 
                     // Mutex locking is limited to this scope
                     {
@@ -141,6 +166,10 @@ impl CairoWorker {
                             let mut stats = self.stats.lock().expect("Failed to get mutex");
 
                             if !stats.coverage_db.contains_key(&vec_trace) {
+                                // Count the coverage found thanks to the dictionary
+                                if mutator.dict_mutations > 0 {
+                                    stats.dict_coverage += 1;
+                                }
                                 // Save input to global input database
                                 if stats.input_db.insert(fuzz_input.clone()) {
                                     // Copy in the input list
@@ -160,6 +189,18 @@ impl CairoWorker {
                         }
                     }
                 }
+                Err(e) if is_assume(&e) => {
+                    // The input does not satisfy the assumptions of the function, discard it
+                    local_stats.discarded += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.discarded += 1;
+                }
+                Err(e) if is_expected_revert(&e, &self.expected_reverts) => {
+                    // The contract reverted on purpose, count it without saving a crash
+                    local_stats.expected_reverts += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.expected_reverts += 1;
+                }
                 Err(e) => {
                     // Mutex locking is limited to this scope
                     {
@@ -187,6 +228,11 @@ impl CairoWorker {
                             let mut crash_file_lock =
                                 self.crash_file.lock().expect("Failed to get mutex");
                             crash_file_lock.crashes.push(fuzz_input.to_vec());
+                            crash_file_lock.records.push(CrashRecord {
+                                input: fuzz_input.to_vec(),
+                                error: e.clone(),
+                                ..Default::default()
+                            });
                             crash_file_lock.dump_json();
 
                             println!(
@@ -213,15 +259,33 @@ impl CairoWorker {
     pub fn replay(&mut self, inputs: Vec<Arc<Vec<Felt252>>>) {
         // Local stats database
         let mut local_stats = Statistics::default();
-        let cairo_runner = cairo_runner::RunnerCairo::new(&self.program);
+        // Start the reference implementation if provided
+        let mut reference = match self.reference_command.is_empty() {
+            true => None,
+            false => Some(Reference::new(&self.reference_command)),
+        };
         for input in inputs {
             let fuzz_input = input.clone();
-            match cairo_runner
+            let mut result = self
+                .cairo_runner
                 .clone()
-                .runner(&self.function.name, &fuzz_input)
-            {
-                Ok(traces) => {
-                    let vec_trace: Vec<(u32, u32)> = traces.expect("Could not get traces");
+                .runner(&self.function.name, &fuzz_input);
+            // compare the return values with the reference implementation
+            if let Some(reference) = &mut reference {
+                result = reference.check(&self.function.name, &fuzz_input, result);
+            }
+            // check the execution with the oracles
+            result = check_oracles(&self.oracles, &fuzz_input, result);
+            match result {
+                Ok(output) => {
+                    let output = output.expect("Could not get traces");
+                    println!(
+                        "WORKER {} -- INPUT => {:?} -- RETURN => {}",
+                        self.worker_id,
+                        &input,
+                        format_felts(&output.retdata)
+                    );
+                    let vec_trace = output.trace;
                     // Mutex locking is limited to this scope
                     {
                         let stats = self.stats.lock().expect("Failed to get mutex");
@@ -262,6 +326,18 @@ impl CairoWorker {
                         }
                     }
                 }
+                Err(e) if is_assume(&e) => {
+                    // The input does not satisfy the assumptions of the function, discard it
+                    local_stats.discarded += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.discarded += 1;
+                }
+                Err(e) if is_expected_revert(&e, &self.expected_reverts) => {
+                    // The contract reverted on purpose, count it without saving a crash
+                    local_stats.expected_reverts += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.expected_reverts += 1;
+                }
                 Err(e) => {
                     // Mutex locking is limited to this scope
                     {
@@ -1,5 +1,6 @@
 //use crate::cairo_vm::cairo_types::Felt252;
 use crate::json::json_parser::Function;
+use crate::runner::runner::{Event, L2ToL1Message, StorageWrite};
 use chrono::DateTime;
 use chrono::Utc;
 use felt::Felt252;
@@ -12,6 +13,22 @@ use std::path::Path;
 use std::process;
 use std::time::SystemTime;
 
+/// Crashing input with the error it raised
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CrashRecord {
+    pub input: Vec<Felt252>,
+    pub error: String,
+    /// Events emitted by the execution, when it succeeded but was flagged
+    #[serde(default)]
+    pub events: Vec<Event>,
+    /// Messages sent to L1 by the execution, when it succeeded but was flagged
+    #[serde(default)]
+    pub messages: Vec<L2ToL1Message>,
+    /// Storage written by the execution, including the writes made before a failure
+    #[serde(default)]
+    pub storage: Vec<StorageWrite>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct CrashFile {
     pub workspace: String,
@@ -19,6 +36,8 @@ pub struct CrashFile {
     pub name: String,
     pub args: Vec<String>,
     pub crashes: Vec<Vec<Felt252>>,
+    #[serde(default)]
+    pub records: Vec<CrashRecord>,
 }
 
 impl CrashFile {
@@ -41,6 +60,7 @@ impl CrashFile {
             name: function.name.clone(),
             args: function.type_args.clone(),
             crashes: Vec::<Vec<Felt252>>::new(),
+            records: Vec::new(),
         }
     }
 
@@ -88,6 +108,7 @@ impl CrashFile {
                 })
                 .collect(),
             crashes: crashes_vec,
+            records: Vec::new(),
         };
     }
 
@@ -173,6 +194,7 @@ impl CrashFile {
             name: function_name.clone(),
             args: data_args,
             crashes: inputs,
+            records: Vec::new(),
         };
     }
 
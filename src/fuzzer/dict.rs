@@ -1,68 +1,255 @@
+use std::collections::HashSet;
 use std::fs;
+use std::process;
 
 use felt::Felt252;
+use num_traits::{One, ToPrimitive};
+use serde_json::Value;
 
-//use crate::mutator::mutator::InputDatabase;
+use crate::json::json_parser::parse_felt;
+use crate::mutator::mutator::InputDatabase;
 
-/* pub fn read_dict(path: &String) -> Dict {
-    println!("\t\t\t\t\t\t\tReading and parsing dict: {}", path);
-    let contents = fs::read_to_string(path).expect("Could not read dictionnary");
-    let lines = contents.split('\n');
-
-    let mut data: Vec<Felt252> = Vec::new();
-
-    for line in lines {
-        let mut parts = line.trim().split('=');
-        if let Some(_) = parts.next() {
-            if let Some(value) = parts.next() {
-                let val: Result<u128, _> = value.to_owned().parse();
-                data.push(Felt252::from(
-                    val.expect("could not get u128 from value in dict"),
-                ));
-            }
-        }
-    }
-    return Dict { inputs: data };
-} */
+/// Flag of the instructions whose second operand is an immediate
+const OP1_IMM: u64 = 1 << 2;
+/// Flags of the instructions whose immediate is a jump or call offset
+const JUMP_FLAGS: u64 = (1 << 7) | (1 << 8) | (1 << 9) | (1 << 12);
 
+/// Dictionary of values used to seed and mutate the inputs
+///
+/// One value per line, with an optional key and argument positions:
+/// ```text
+/// # comment
+/// key1=1234
+/// key2=0x4d2
+/// key3=-1
+/// key4='owner'
+/// key5@0,2=1000
+/// ```
+/// Values are decimal or hexadecimal felts lower than P, negative values
+/// are taken modulo P and quoted values are short strings (up to 31 characters).
+/// A value tagged with `@positions` is only used for these arguments of the function.
 #[derive(Debug, Clone, Default)]
 pub struct Dict {
     pub inputs: Vec<Felt252>,
+    /// Arguments where each value can be used, any argument if empty
+    pub positions: Vec<Vec<usize>>,
+    /// Values with their positions already in the dictionary
+    entries: HashSet<(Felt252, Vec<usize>)>,
+}
+
+/// Parse the argument positions of a key written as `key@0,2`
+fn parse_positions(key: &str) -> Result<Vec<usize>, String> {
+    match key.split_once('@') {
+        Some((_, positions)) => positions
+            .split(',')
+            .map(|position| {
+                position
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid argument position {}", position))
+            })
+            .collect(),
+        None => Ok(Vec::new()),
+    }
 }
+
 impl Dict {
     pub fn read_dict(path: &String) -> Dict {
         println!("\t\t\t\t\t\t\tReading and parsing dict: {}", path);
         let contents = fs::read_to_string(path).expect("Could not read dictionnary");
-        let lines = contents.split('\n');
-
-        let mut data: Vec<Felt252> = Vec::new();
-
-        for line in lines {
-            let mut parts = line.trim().split('=');
-            if let Some(_) = parts.next() {
-                if let Some(value) = parts.next() {
-                    let val: Result<u128, _> = value.to_owned().parse();
-                    data.push(Felt252::from(
-                        val.expect("could not get u128 from value in dict"),
-                    ));
+        match Dict::parse(&contents) {
+            Ok(dict) => dict,
+            Err(e) => {
+                eprintln!("Error: dictionary {} {}", path, e);
+                process::exit(1)
+            }
+        }
+    }
+
+    /// Parse the contents of a dictionary file
+    pub fn parse(contents: &str) -> Result<Dict, String> {
+        let mut dict = Dict::default();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // The key is optional
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) if !key.contains(|c| c == '\'' || c == '"') => {
+                    (key.trim(), value.trim())
                 }
+                _ => ("", line),
+            };
+            let error = |e: String| format!("line {}: {}", index + 1, e);
+            let value = parse_felt(value).map_err(error)?;
+            let positions = parse_positions(key).map_err(error)?;
+            dict.push(value, positions);
+        }
+        return Ok(dict);
+    }
+
+    /// Add a value if it is not in the dictionary with the same positions
+    pub fn push(&mut self, value: Felt252, positions: Vec<usize>) {
+        if self.entries.insert((value.clone(), positions.clone())) {
+            self.inputs.push(value);
+            self.positions.push(positions);
+        }
+    }
+
+    /// Shift the positions by the `offset` of the function arguments in the input
+    pub fn shift_positions(&mut self, offset: usize) {
+        for positions in self.positions.iter_mut() {
+            for position in positions.iter_mut() {
+                *position += offset;
             }
         }
-        return Dict { inputs: data };
+        self.entries = self
+            .inputs
+            .iter()
+            .cloned()
+            .zip(self.positions.iter().cloned())
+            .collect();
+    }
+
+    /// Values that can be used for the argument `position`
+    pub fn values_at(&self, position: usize) -> Vec<&Felt252> {
+        self.inputs
+            .iter()
+            .zip(&self.positions)
+            .filter(|(_, positions)| positions.is_empty() || positions.contains(&position))
+            .map(|(value, _)| value)
+            .collect()
+    }
+
+    /// Extract the immediates of the instructions in the `data` of a Cairo program
+    /// or a Starknet contract artifact, with their negation and the values next to them
+    /// Constants compared in asserts and short strings are immediates, jump and
+    /// call offsets are skipped
+    pub fn from_program(contents: &String) -> Dict {
+        let json: Value = serde_json::from_str(contents).expect("JSON was not well-formatted");
+        let program = json.get("program").unwrap_or(&json);
+        let words: Vec<Felt252> = match program["data"].as_array() {
+            Some(data) => data
+                .iter()
+                .filter_map(|word| word.as_str().and_then(|word| parse_felt(word).ok()))
+                .collect(),
+            None => Vec::new(),
+        };
+        let mut dict = Dict::default();
+        let mut pc = 0;
+        while pc < words.len() {
+            // Instructions are encoded on 63 bits, the flags are above the 3 offsets
+            let flags = match words[pc].to_biguint().to_u64() {
+                Some(word) if word >> 63 == 0 => word >> 48,
+                _ => {
+                    pc += 1;
+                    continue;
+                }
+            };
+            if flags & OP1_IMM == 0 || pc + 1 >= words.len() {
+                pc += 1;
+                continue;
+            }
+            if flags & JUMP_FLAGS == 0 {
+                // `x == C` is compiled as `x + (-C)`, so the constant is the negated immediate
+                let immediate = words[pc + 1].clone();
+                for constant in [-immediate.clone(), immediate] {
+                    for value in [
+                        constant.clone(),
+                        constant.clone() - Felt252::one(),
+                        constant + Felt252::one(),
+                    ] {
+                        dict.push(value, Vec::new());
+                    }
+                }
+            }
+            pc += 2;
+        }
+        return dict;
+    }
+
+    /// Add the values of `other` that are not in the dictionary
+    pub fn merge(&mut self, other: &Dict) {
+        for (value, positions) in other.inputs.iter().zip(&other.positions) {
+            self.push(value.clone(), positions.clone());
+        }
     }
 }
-/* impl InputDatabase for Dict {
+impl InputDatabase for Dict {
     fn num_inputs(&self) -> usize {
         return self.inputs.len();
     }
-    // Dict take Felt252
-    // Send it to the mutator
-    // Via  CLI the user will be able to choose between the different mutators
-    // We will initiate the mutatore choosen by the user
-    // The mutator will take the dictionnary and will use the values
-    // the mutator will return a Felt252
-    fn input(&self, idx: usize) -> Option<Felt252> {
-        let value: Felt252 = self.inputs[idx].clone();
-        return Some(value.clone());
+
+    fn input(&self, idx: usize) -> Option<Vec<Felt252>> {
+        return self.inputs.get(idx).map(|value| vec![value.clone()]);
+    }
+
+    fn positions(&self, idx: usize) -> Vec<usize> {
+        return self.positions.get(idx).cloned().unwrap_or_default();
     }
-} */
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dict_from_program() {
+        let contents = fs::read_to_string("tests/fuzzinglabs.json").expect("Could not read file");
+        let dict = Dict::from_program(&contents);
+        // Short strings compared in the function
+        for value in ['f', 'u', 'z', 's'] {
+            assert!(dict.inputs.contains(&Felt252::from(value as u64)));
+        }
+    }
+
+    #[test]
+    fn test_dict_push() {
+        let mut dict = Dict::default();
+        dict.push(Felt252::from(1), Vec::new());
+        dict.push(Felt252::from(1), vec![0]);
+        dict.push(Felt252::from(1), Vec::new());
+        dict.push(Felt252::from(2), Vec::new());
+        // The same value can be restricted to other positions
+        assert_eq!(
+            dict.inputs,
+            vec![Felt252::from(1), Felt252::from(1), Felt252::from(2)]
+        );
+        let mut other = Dict::default();
+        other.push(Felt252::from(2), Vec::new());
+        other.push(Felt252::from(3), Vec::new());
+        dict.merge(&other);
+        assert_eq!(dict.inputs.len(), 4);
+    }
+
+    #[test]
+    fn test_dict_format() {
+        let dict = Dict::parse(
+            "# comment\nkey1=1234\nkey2=0x4d2\nkey3=-1\nkey4='owner'\nkey5@0,2=7\n\n42\n",
+        )
+        .expect("Could not parse dict");
+        assert_eq!(dict.inputs[0], Felt252::from(1234));
+        // 0x4d2 is a duplicate of 1234
+        assert_eq!(dict.inputs.len(), 5);
+        assert_eq!(dict.inputs[1], -Felt252::from(1));
+        assert_eq!(dict.inputs[2], Felt252::from_bytes_be(b"owner"));
+        assert_eq!(dict.positions[3], vec![0, 2]);
+        assert_eq!(dict.values_at(1).len(), 4);
+        let error = Dict::parse("key1=1\nkey2=abc").expect_err("Invalid value parsed");
+        assert!(error.starts_with("line 2:"));
+        let error = Dict::parse(&format!("key={}", Felt252::prime())).expect_err("P parsed");
+        assert!(error.contains("field prime"));
+    }
+
+    #[test]
+    fn test_dict_shift_positions() {
+        let mut dict = Dict::parse("key1@0,2=7\n42\n").expect("Could not parse dict");
+        // Arguments after the 9 felts of a fuzzed execution context
+        dict.shift_positions(9);
+        assert_eq!(dict.positions[0], vec![9, 11]);
+        assert!(dict.positions[1].is_empty());
+        assert_eq!(dict.values_at(0).len(), 1);
+        assert_eq!(dict.values_at(9).len(), 2);
+    }
+}
@@ -11,12 +11,26 @@ use crate::{
     fuzzer::cairo_worker::CairoWorker,
     fuzzer::dict::Dict,
     fuzzer::starknet_worker::StarknetWorker,
-    json::json_parser::{parse_json, parse_starknet_json, Function},
+    json::json_parser::{
+        get_external_functions, get_invariant_functions, parse_json, parse_starknet_json, Function,
+    },
+    runner::{
+        access_control::AccessControl,
+        cairo_runner::RunnerCairo,
+        erc20::Erc20,
+        manifest::Manifest,
+        mock::{Mocks, DEFAULT_MOCK_ARTIFACT},
+        model::{ModelFactory, DEFAULT_SEQUENCE_LENGTH},
+        snapshot::Snapshot,
+        starknet_runner::{context_type_args, RunnerStarknet, Sequence, CONTEXT_FIELDS},
+        storage_decoder::StorageDecoder,
+    },
 };
 
-use super::{corpus_crash::CrashFile, corpus_input::InputFile, stats::Statistics};
+use super::{corpus_crash::CrashFile, corpus_input::InputFile, oracle::Oracle, stats::Statistics};
 use cairo_rs::types::program::Program;
 use felt::Felt252;
+use num_traits::Zero;
 use rand::Rng;
 use starknet_rs::services::api::contract_classes::deprecated_contract_class::ContractClass;
 use std::io::Write;
@@ -35,6 +49,12 @@ pub struct Fuzzer {
     pub program: Option<Program>,
     /// Contract_class for starknet-rs
     pub contract_class: Option<ContractClass>,
+    /// Runner for cairo programs
+    pub cairo_runner: Option<RunnerCairo>,
+    /// Runner for starknet contracts
+    pub starknet_runner: Option<RunnerStarknet>,
+    /// Invariants of the starknet contract
+    pub invariants: Vec<Function>,
     /// Contract function to fuzz
     pub function: Function,
     /// Store local/on-disk logs
@@ -51,6 +71,9 @@ pub struct Fuzzer {
     pub input_file: Arc<Mutex<InputFile>>,
     /// Crashes file path
     pub crash_file: Arc<Mutex<CrashFile>>,
+    /// Inputs and crashes as loaded from the files, before the entries of
+    /// another shape were left out
+    pub loaded_corpus: (InputFile, CrashFile),
     /// Number of second the fuzzing session will last
     pub run_time: Option<u64>,
     /// Starting time of the fuzzer
@@ -65,6 +88,18 @@ pub struct Fuzzer {
     pub proptesting: bool,
     /// Dictionnary struct
     pub dict: Dict,
+    /// Error messages of the expected reverts
+    pub expected_reverts: Vec<String>,
+    /// Command of the reference implementation
+    pub reference_command: String,
+    /// Oracles checking every execution
+    pub oracles: Vec<Arc<dyn Oracle>>,
+    /// Number of calls of the sequences executed with a model
+    pub sequence_length: usize,
+    /// Execution context taken from the fuzz input
+    pub fuzz_context: bool,
+    /// Mock contracts returning values taken from the fuzz input
+    pub mocks: Option<Mocks>,
 }
 
 impl Fuzzer {
@@ -84,11 +119,62 @@ impl Fuzzer {
         // Read contract JSON artifact and get its content
         let contents = fs::read_to_string(&config.contract_file)
             .expect("Should have been able to read the file");
+        // Names of the storage variables of the contract, empty for Cairo programs
+        let storage_decoder = StorageDecoder::from_artifact(&contents);
+
+        let sequence_length = match config.sequence_length {
+            0 => DEFAULT_SEQUENCE_LENGTH,
+            length => length,
+        };
+        // Detect the ERC20 entrypoints for the property suite
+        let erc20 = match config.erc20 {
+            true => match Erc20::detect(&contents, sequence_length) {
+                Some(erc20) => Some(erc20),
+                None => {
+                    eprintln!("Error: Could not find the ERC20 entrypoints in the ABI");
+                    process::exit(1)
+                }
+            },
+            false => None,
+        };
+        // Get the external functions called by the access-control detector
+        let access_control = match config.access_control {
+            true => {
+                let access_control = AccessControl::new(
+                    &get_external_functions(&contents),
+                    &storage_decoder,
+                    &config.public_functions,
+                    sequence_length,
+                );
+                if access_control.functions.len() == 0 || access_control.owner_vars.len() == 0 {
+                    eprintln!("Error: The access-control detector needs external functions and an owner storage variable");
+                    process::exit(1);
+                }
+                Some(access_control)
+            }
+            false => None,
+        };
+        // Both modes encode their own sequences of calls in the input
+        if erc20.is_some() && access_control.is_some() {
+            eprintln!(
+                "Error: The ERC20 property suite and the access-control detector can not be combined"
+            );
+            process::exit(1);
+        }
+        // The transfer function is fuzzed if no function is provided with the ERC20 suite
+        // and the first external function with the access-control detector
+        let function_name = match (&erc20, &access_control) {
+            (Some(erc20), _) if config.function_name.is_empty() => erc20.transfer.clone(),
+            (_, Some(access_control)) if config.function_name.is_empty() => {
+                access_control.functions[0].name.clone()
+            }
+            _ => config.function_name.clone(),
+        };
 
         // TODO - remove when support multiple txs
-        let function = match parse_json(&contents, &config.function_name) {
+        let mut function = match parse_json(&contents, &function_name) {
             Some(func) => func,
-            None => match parse_starknet_json(&contents, &config.function_name) {
+            None => match parse_starknet_json(&contents, &function_name) {
                 Some(func) => func,
                 None => {
                     eprintln!("Error: Could not parse json file");
@@ -96,6 +182,53 @@ impl Fuzzer {
                 }
             },
         };
+        let signature = function.type_args.clone();
+        // The fuzzed input of the ERC20 suite is the encoded sequence of token calls
+        if let Some(erc20) = &erc20 {
+            println!(
+                "\t\t\t\t\t\t\tERC20 property suite ({} calls per sequence)",
+                erc20.length
+            );
+            function.num_args = erc20.input_size() as u64;
+            function.type_args = vec!["felt".to_string(); erc20.input_size()];
+        }
+        // The fuzzed input of the access-control detector is the encoded sequence of calls
+        if let Some(access_control) = &access_control {
+            println!(
+                "\t\t\t\t\t\t\tAccess-control detector ({} functions, owner in {})",
+                access_control.functions.len(),
+                access_control.owner_vars.join(", ")
+            );
+            function.num_args = access_control.input_size() as u64;
+            function.type_args = vec!["felt".to_string(); access_control.input_size()];
+        }
+        let fuzz_context = config.fuzz_context;
+        if fuzz_context && !function._starknet {
+            eprintln!("Error: The execution context can only be fuzzed for Starknet contracts");
+            process::exit(1);
+        }
+        // Load the mock contracts
+        let mocks = match config.mocks.is_empty() {
+            true => None,
+            false => {
+                if !function._starknet {
+                    eprintln!("Error: Mock contracts can only be used with Starknet contracts");
+                    process::exit(1);
+                }
+                let artifact = match config.mock_artifact.is_empty() {
+                    true => DEFAULT_MOCK_ARTIFACT.to_string(),
+                    false => config.mock_artifact.clone(),
+                };
+                let mocks = Mocks::new(&artifact, &config.mocks);
+                println!(
+                    "\t\t\t\t\t\t\tMocks loaded {} ({} responses)",
+                    mocks.mocks.len(),
+                    mocks.input_size()
+                );
+                Some(mocks)
+            }
+        };
+        add_environment_args(&mut function, fuzz_context, &mocks);
         // Load inputs from the input file if provided
         let mut inputs: InputFile =
             match config.input_file.is_empty() && config.input_folder.is_empty() {
@@ -107,35 +240,48 @@ impl Fuzzer {
             };
         println!("\t\t\t\t\t\t\tInputs loaded {}", inputs.inputs.len());
 
-        let dict = match &config.dict.is_empty() {
-            true => Dict { inputs: Vec::new() },
+        let mut dict = match &config.dict.is_empty() {
+            true => Dict::default(),
             false => Dict::read_dict(&config.dict),
         };
+        // The positions are relative to the function arguments, after the context
+        let args_offset = match fuzz_context {
+            true => CONTEXT_FIELDS.len(),
+            false => 0,
+        };
+        dict.shift_positions(args_offset);
 
-        let nbr_args = function.num_args;
-        for val in &dict.inputs {
-            let mut value_vec: Vec<Felt252> = Vec::new();
-            value_vec.push(val.clone()); // to ensure that all values of the dict will be in the inputs vector
-            for _ in 0..nbr_args - 1 {
-                value_vec
-                    .push(dict.inputs[rand::thread_rng().gen_range(0..dict.inputs.len())].clone());
+        let nbr_args = function.num_args as usize;
+        for (val, positions) in dict.inputs.iter().zip(&dict.positions) {
+            // to ensure that all values of the dict will be in the inputs vector
+            let position = positions.first().cloned().unwrap_or(args_offset);
+            if position >= nbr_args {
+                continue;
             }
+            // Other arguments take random values allowed at their position
+            let mut value_vec: Vec<Felt252> = (0..nbr_args)
+                .map(|index| {
+                    let values = dict.values_at(index);
+                    match values.is_empty() {
+                        true => Felt252::zero(),
+                        false => values[rand::thread_rng().gen_range(0..values.len())].clone(),
+                    }
+                })
+                .collect();
+            value_vec[position] = val.clone();
             inputs.inputs.push(value_vec);
         }
 
-        // Load existing inputs in shared database
-        if inputs.inputs.len() > 0 {
-            let mut stats_db = stats.lock().expect("Failed to lock stats mutex");
-            for input in &inputs.inputs {
-                if stats_db.input_db.insert(Arc::new(input.clone())) {
-                    stats_db.input_list.push(Arc::new(input.clone()));
-                    stats_db.input_len += 1;
-                }
-            }
-        }
+        // Constants of the bytecode, only used by the mutations
+        let constants = Dict::from_program(&contents);
+        println!(
+            "\t\t\t\t\t\t\tDictionary values extracted {}",
+            constants.inputs.len()
+        );
+        dict.merge(&constants);
 
         // Load crashes from the crash file if provided
-        let crashes: CrashFile =
+        let mut crashes: CrashFile =
             match config.crash_file.is_empty() && config.crash_folder.is_empty() {
                 true => CrashFile::new_from_function(&function, &config.workspace),
                 false => match config.input_folder.is_empty() {
@@ -144,14 +290,14 @@ impl Fuzzer {
                 },
             };
 
-        // Load existing crashes in shared database
-        if crashes.crashes.len() > 0 {
-            let mut stats_db = stats.lock().expect("Failed to lock stats mutex");
-            for input in &crashes.crashes {
-                stats_db.crash_db.insert(Arc::new(input.clone()));
-                stats_db.crashes += 1;
-            }
-        }
+        // Load existing inputs and crashes in shared database
+        let loaded_corpus = (inputs.clone(), crashes.clone());
+        load_corpus(
+            &mut stats.lock().expect("Failed to lock stats mutex"),
+            &mut inputs,
+            &mut crashes,
+            &function,
+        );
 
         let program = if !function._starknet {
             Some(
@@ -167,6 +313,122 @@ impl Fuzzer {
             None
         };
 
+        // Load the invariants checked after each call
+        let invariants = if function._starknet {
+            get_invariant_functions(&contents)
+        } else {
+            Vec::new()
+        };
+        if invariants.len() > 0 {
+            println!("\t\t\t\t\t\t\tInvariants loaded {}", invariants.len());
+        }
+        let mut cairo_runner = match &program {
+            Some(program) => Some(RunnerCairo::new(program)),
+            None => None,
+        };
+        // Load the initial storage of the contracts
+        let snapshot = match config.state_file.is_empty() {
+            true => Snapshot::default(),
+            false => {
+                let snapshot = Snapshot::load(&config.state_file, &storage_decoder);
+                println!(
+                    "\t\t\t\t\t\t\tStorage slots loaded {}",
+                    snapshot.entries.len()
+                );
+                snapshot
+            }
+        };
+        // Load the other contracts deployed with the fuzzed contract
+        let manifest = match config.manifest_file.is_empty() {
+            true => Manifest::default(),
+            false => {
+                let manifest = Manifest::load(&config.manifest_file);
+                println!(
+                    "\t\t\t\t\t\t\tContracts deployed {}",
+                    manifest.deployments.len()
+                );
+                manifest
+            }
+        };
+        let mut starknet_runner = match &contract_class {
+            Some(contract_class) => {
+                let runner = RunnerStarknet::new(contract_class)
+                    .invariants(&invariants)
+                    .fuzz_context(fuzz_context)
+                    .snapshot(&snapshot)
+                    .manifest(&manifest)
+                    .mocks(&mocks)
+                    .entry_point(&function.decorators)
+                    .storage_decoder(&storage_decoder);
+                let runner = match &erc20 {
+                    Some(erc20) => runner.erc20(erc20),
+                    None => runner,
+                };
+                match &access_control {
+                    Some(access_control) => Some(runner.access_control(access_control)),
+                    None => Some(runner),
+                }
+            }
+            None => None,
+        };
+
+        // Load the other version of the contract for differential fuzzing
+        if !config.diff_contract_file.is_empty() {
+            let diff_contents = fs::read_to_string(&config.diff_contract_file)
+                .expect("Should have been able to read the file");
+            let diff_function = match parse_json(&diff_contents, &function_name) {
+                Some(func) => func,
+                None => match parse_starknet_json(&diff_contents, &function_name) {
+                    Some(func) => func,
+                    None => {
+                        eprintln!("Error: Could not parse differential json file");
+                        process::exit(1)
+                    }
+                },
+            };
+            if diff_function._starknet != function._starknet || diff_function.type_args != signature
+            {
+                eprintln!(
+                    "Error: {} has a different signature in {}",
+                    function.name, config.diff_contract_file
+                );
+                process::exit(1);
+            }
+            println!(
+                "\t\t\t\t\t\t\tDifferential fuzzing against {}",
+                config.diff_contract_file
+            );
+            if function._starknet {
+                let diff_contract_class = ContractClass::from_str(diff_contents.as_str())
+                    .expect("could not get contractclass");
+                starknet_runner = starknet_runner.map(|runner| {
+                    runner.differential(
+                        &RunnerStarknet::new(&diff_contract_class)
+                            .invariants(&get_invariant_functions(&diff_contents))
+                            .snapshot(&snapshot)
+                            .manifest(&manifest)
+                            .mocks(&mocks)
+                            .entry_point(&function.decorators),
+                    )
+                });
+            } else {
+                let diff_program =
+                    Program::from_bytes(&diff_contents.as_bytes(), Some(&function.name))
+                        .expect("Failed to deserialize Program");
+                cairo_runner = cairo_runner
+                    .map(|runner| runner.differential(&RunnerCairo::new(&diff_program)));
+            }
+        }
+
+        // Deploy the contracts once, a failing constructor or address collision stops here
+        let starknet_runner = starknet_runner.map(|runner| match runner.deploy() {
+            Ok(runner) => runner,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1)
+            }
+        });
+
         // Setup the mutex for the inputs corpus and crash corpus
         let inputs = Arc::new(Mutex::new(inputs));
         let crashes = Arc::new(Mutex::new(crashes));
@@ -184,17 +446,89 @@ impl Fuzzer {
             program: program,
             dict: dict,
             contract_class: contract_class,
+            cairo_runner: cairo_runner,
+            starknet_runner: starknet_runner,
+            invariants: invariants,
             function: function.clone(),
             start_time: Instant::now(),
             seed: seed,
             input_file: inputs,
             crash_file: crashes,
+            loaded_corpus: loaded_corpus,
             workspace: config.workspace.clone(),
             running_workers: 0,
             starknet: function._starknet,
             iter: config.iter,
             proptesting: config.proptesting,
+            expected_reverts: config.expected_reverts.clone(),
+            reference_command: config.reference_command.clone(),
+            oracles: Vec::new(),
+            sequence_length: sequence_length,
+            fuzz_context: fuzz_context,
+            mocks: mocks,
+        }
+    }
+
+    /// Register an oracle checking every execution of the function
+    pub fn register_oracle(&mut self, oracle: Arc<dyn Oracle>) {
+        self.oracles.push(oracle);
+    }
+
+    /// Register the model of the contract
+    /// The inputs become sequences of calls to the fuzzed function and to the
+    /// functions of the model, executed on the same state
+    pub fn register_model(&mut self, model: ModelFactory) {
+        let starknet_runner = match &self.starknet_runner {
+            Some(starknet_runner) => starknet_runner.clone(),
+            None => {
+                eprintln!("Error: Model-based testing requires a Starknet contract");
+                process::exit(1)
+            }
+        };
+        if let Some(mode) = starknet_runner.sequence_mode() {
+            eprintln!("Error: A model can not be combined with the {}", mode);
+            process::exit(1)
+        }
+        let mut functions = Vec::<Function>::new();
+        let mut names = vec![self.function.name.clone()];
+        names.extend(model().functions());
+        for name in names {
+            if functions.iter().any(|function| function.name == name) {
+                continue;
+            }
+            match parse_starknet_json(&self.contract_content, &name) {
+                Some(function) => functions.push(function),
+                None => {
+                    eprintln!("Error: Could not find function {} of the model", name);
+                    process::exit(1)
+                }
+            }
+        }
+        let sequence = Sequence {
+            functions: functions,
+            length: self.sequence_length,
+            model: model,
+        };
+        println!(
+            "\t\t\t\t\t\t\tModel loaded ({} functions, {} calls per sequence)",
+            sequence.functions.len(),
+            sequence.length
+        );
+        // The fuzzed input is the encoded sequence
+        self.function.num_args = sequence.input_size() as u64;
+        self.function.type_args = vec!["felt".to_string(); sequence.input_size()];
+        add_environment_args(&mut self.function, self.fuzz_context, &self.mocks);
+        // Reload the corpus from the loaded files, only the inputs of a previous
+        // sequence campaign are kept
+        {
+            let (mut inputs, mut crashes) = self.loaded_corpus.clone();
+            let mut stats = self.stats.lock().expect("Failed to lock stats mutex");
+            *stats = Statistics::default();
+            load_corpus(&mut stats, &mut inputs, &mut crashes, &self.function);
+            *self.input_file.lock().expect("Failed to get mutex") = inputs;
+            *self.crash_file.lock().expect("Failed to get mutex") = crashes;
         }
+        self.starknet_runner = Some(starknet_runner.sequence(&sequence));
     }
 
     /// Fuzz
@@ -206,37 +540,47 @@ impl Fuzzer {
             let function = self.function.clone();
             let input_file = self.input_file.clone();
             let crash_file = self.crash_file.clone();
-            let program = self.program.clone();
-            let contract_class = self.contract_class.clone();
+            let cairo_runner = self.cairo_runner.clone();
+            let starknet_runner = self.starknet_runner.clone();
             let seed = self.seed + (i as u64);
             let starknet = self.starknet;
             let iter = self.iter;
-            //let dict = self.dict.clone();
+            let expected_reverts = self.expected_reverts.clone();
+            let reference_command = self.reference_command.clone();
+            let oracles = self.oracles.clone();
+            let dict = self.dict.clone();
             // Spawn threads
             std::thread::spawn(move || {
                 if !starknet {
                     let cairo_worker = CairoWorker::new(
                         stats,
                         i,
-                        program.expect("Could not get Cairo Program (None)"),
+                        cairo_runner.expect("Could not get Cairo runner (None)"),
                         function,
                         seed,
                         input_file,
                         crash_file,
                         iter,
-                        //dict,
+                        expected_reverts,
+                        reference_command,
+                        oracles,
+                        dict,
                     );
                     cairo_worker.fuzz();
                 } else {
                     let starknet_worker = StarknetWorker::new(
                         stats,
                         i,
-                        contract_class.expect("Could not get Cairo Program (None)"),
+                        starknet_runner.expect("Could not get Starknet runner (None)"),
                         function,
                         seed,
                         input_file,
                         crash_file,
                         iter,
+                        expected_reverts,
+                        reference_command,
+                        oracles,
+                        dict,
                     );
                     starknet_worker.fuzz();
                 }
@@ -281,35 +625,45 @@ impl Fuzzer {
             let input_file = self.input_file.clone();
             let crash_file = self.crash_file.clone();
             let starknet = self.starknet.clone();
-            let program = self.program.clone();
-            let contract_class = self.contract_class.clone();
+            let cairo_runner = self.cairo_runner.clone();
+            let starknet_runner = self.starknet_runner.clone();
             let iter = if self.proptesting { self.iter } else { 0 };
-            //let dict = self.dict.clone();
+            let expected_reverts = self.expected_reverts.clone();
+            let reference_command = self.reference_command.clone();
+            let oracles = self.oracles.clone();
+            let dict = self.dict.clone();
             let chunk = chunks[i].clone();
             threads.push(std::thread::spawn(move || {
                 if !starknet {
                     let mut cairo_worker = CairoWorker::new(
                         stats_thread,
                         i as i32,
-                        program.expect("Could not get Cairo Program (None)"),
+                        cairo_runner.expect("Could not get Cairo runner (None)"),
                         function,
                         seed,
                         input_file,
                         crash_file,
                         iter,
-                        //dict
+                        expected_reverts,
+                        reference_command,
+                        oracles,
+                        dict,
                     );
                     cairo_worker.replay(chunk);
                 } else {
                     let mut starknet_worker = StarknetWorker::new(
                         stats_thread,
                         i as i32,
-                        contract_class.expect("Could not get Cairo Program (None)"),
+                        starknet_runner.expect("Could not get Starknet runner (None)"),
                         function,
                         seed,
                         input_file,
                         crash_file,
                         iter,
+                        expected_reverts,
+                        reference_command,
+                        oracles,
+                        dict,
                     );
                     starknet_worker.replay(chunk);
                 }
@@ -369,26 +723,32 @@ impl Fuzzer {
                 let fuzz_case = stats.fuzz_cases;
                 print!(
                     "{:12.2} uptime | {:9} fuzz cases | {:12.2} fcps | \
-                            {:6} coverage | {:6} inputs | {:6} crashes [{:6} unique]\n",
+                            {:6} coverage [{:6} dict] | {:6} inputs | {:6} crashes [{:6} unique] | {:6} discarded | {:6} reverts\n",
                     uptime,
                     fuzz_case,
                     fuzz_case as f64 / uptime,
                     stats.coverage_db.len(),
+                    stats.dict_coverage,
                     stats.input_len,
                     stats.crashes,
-                    stats.crash_db.len()
+                    stats.crash_db.len(),
+                    stats.discarded,
+                    stats.expected_reverts
                 );
                 // Writing inside logging file
                 if let Some(ref mut file) = log {
                     write!(
                         file,
-                        "{:12.0} {:7} {:8} {:5} {:6} {:6}\n",
+                        "{:12.0} {:7} {:8} {:5} {:6} {:6} {:6} {:6} {:6}\n",
                         uptime,
                         fuzz_case,
                         stats.coverage_db.len(),
                         stats.input_len,
                         stats.crashes,
-                        stats.crash_db.len()
+                        stats.crash_db.len(),
+                        stats.discarded,
+                        stats.expected_reverts,
+                        stats.dict_coverage
                     )
                     .expect("Failed to write logs in log file");
                     file.flush().expect("Failed to flush the file");
@@ -412,14 +772,93 @@ impl Fuzzer {
     }
 }
 
+/// Load the inputs and crashes of the corpus in the shared databases
+/// The entries of another size were recorded for another shape of input, the
+/// workers can not execute them. The loaded files are then left untouched and
+/// the entries of the current shape are saved in new files
+fn load_corpus(
+    stats: &mut Statistics,
+    inputs: &mut InputFile,
+    crashes: &mut CrashFile,
+    function: &Function,
+) {
+    let size = function.num_args as usize;
+    let mut dropped = 0;
+    if inputs.args != function.type_args || inputs.inputs.iter().any(|input| input.len() != size) {
+        let kept: Vec<Vec<Felt252>> = inputs
+            .inputs
+            .iter()
+            .filter(|input| input.len() == size)
+            .cloned()
+            .collect();
+        dropped += inputs.inputs.len() - kept.len();
+        *inputs = InputFile {
+            inputs: kept,
+            ..InputFile::new_from_function(function, &inputs.workspace)
+        };
+    }
+    if crashes.args != function.type_args || crashes.crashes.iter().any(|input| input.len() != size)
+    {
+        let kept: Vec<Vec<Felt252>> = crashes
+            .crashes
+            .iter()
+            .filter(|input| input.len() == size)
+            .cloned()
+            .collect();
+        dropped += crashes.crashes.len() - kept.len();
+        *crashes = CrashFile {
+            crashes: kept,
+            records: crashes
+                .records
+                .iter()
+                .filter(|record| record.input.len() == size)
+                .cloned()
+                .collect(),
+            ..CrashFile::new_from_function(function, &crashes.workspace)
+        };
+    }
+    if dropped > 0 {
+        println!(
+            "\t\t\t\t\t\t\tInputs dropped {} (size != {}), the corpus is saved in new files",
+            dropped, size
+        );
+    }
+    for input in &inputs.inputs {
+        if stats.input_db.insert(Arc::new(input.clone())) {
+            stats.input_list.push(Arc::new(input.clone()));
+            stats.input_len += 1;
+        }
+    }
+    for input in &crashes.crashes {
+        stats.crash_db.insert(Arc::new(input.clone()));
+        stats.crashes += 1;
+    }
+}
+
+/// Add the arguments of the execution context at the beginning of the input
+/// and the responses of the mocks at the end
+fn add_environment_args(function: &mut Function, fuzz_context: bool, mocks: &Option<Mocks>) {
+    if fuzz_context {
+        function.num_args += CONTEXT_FIELDS.len() as u64;
+        function.type_args = [context_type_args(), function.type_args.clone()].concat();
+    }
+    if let Some(mocks) = mocks {
+        function.num_args += mocks.input_size() as u64;
+        function.type_args.extend(mocks.type_args());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::panic;
+    use felt::Felt252;
     use std::{thread, time::Duration};
 
     use crate::cli::config::Config;
+    use crate::fuzzer::{corpus_crash::CrashFile, corpus_input::InputFile, stats::Statistics};
+    use crate::json::json_parser::Function;
 
-    use super::Fuzzer;
+    use super::{load_corpus, Fuzzer};
     #[test]
     fn test_loading_config_file() {
         let config_file = "tests/config.json".to_string();
@@ -486,6 +925,7 @@ mod tests {
             iter,
             proptesting,
             dict,
+            ..Default::default()
         };
         let fuzzer = Fuzzer::new(&config);
         assert_eq!(fuzzer.cores, 1);
@@ -528,6 +968,7 @@ mod tests {
             iter,
             proptesting,
             dict,
+            ..Default::default()
         };
         // create the fuzzer
         let mut fuzzer = Fuzzer::new(&config);
@@ -583,6 +1024,7 @@ mod tests {
             iter,
             proptesting,
             dict,
+            ..Default::default()
         };
         // create the fuzzer
         let mut fuzzer = Fuzzer::new(&config);
@@ -639,6 +1081,7 @@ mod tests {
             iter,
             proptesting,
             dict,
+            ..Default::default()
         };
         // create the fuzzer
         let mut fuzzer = Fuzzer::new(&config);
@@ -684,6 +1127,7 @@ mod tests {
             iter,
             proptesting,
             dict,
+            ..Default::default()
         };
         // create the fuzzer
         let mut fuzzer = Fuzzer::new(&config);
@@ -709,4 +1153,54 @@ mod tests {
             0
         );
     }
+
+    #[test]
+    fn test_load_corpus() {
+        let function = Function {
+            name: "Fuzz_test".to_string(),
+            entrypoint: "0".to_string(),
+            num_args: 3,
+            type_args: vec!["felt".to_string(); 3],
+            hints: false,
+            decorators: Vec::new(),
+            _starknet: false,
+        };
+        let mut stats = Statistics::default();
+        let mut inputs = InputFile {
+            path: "inputs.json".to_string(),
+            args: function.type_args.clone(),
+            inputs: vec![vec![Felt252::from(1); 3], vec![Felt252::from(2); 5]],
+            ..Default::default()
+        };
+        let mut crashes = CrashFile {
+            path: "crashes.json".to_string(),
+            args: function.type_args.clone(),
+            crashes: vec![vec![Felt252::from(3); 3], vec![Felt252::from(4); 2]],
+            ..Default::default()
+        };
+        load_corpus(&mut stats, &mut inputs, &mut crashes, &function);
+        // The inputs of another shape are not loaded and the files are not overwritten
+        assert_eq!(inputs.inputs, vec![vec![Felt252::from(1); 3]]);
+        assert_eq!(crashes.crashes, vec![vec![Felt252::from(3); 3]]);
+        assert_ne!(inputs.path, "inputs.json");
+        assert_ne!(crashes.path, "crashes.json");
+        assert_eq!(stats.input_len, 1);
+        assert_eq!(stats.input_list.len(), 1);
+        assert_eq!(stats.crash_db.len(), 1);
+        // A corpus of the same shape keeps its files
+        let mut inputs = InputFile {
+            path: "inputs.json".to_string(),
+            args: function.type_args.clone(),
+            inputs: vec![vec![Felt252::from(1); 3]],
+            ..Default::default()
+        };
+        let mut crashes = CrashFile {
+            path: "crashes.json".to_string(),
+            args: function.type_args.clone(),
+            ..Default::default()
+        };
+        load_corpus(&mut stats, &mut inputs, &mut crashes, &function);
+        assert_eq!(inputs.path, "inputs.json");
+        assert_eq!(crashes.path, "crashes.json");
+    }
 }
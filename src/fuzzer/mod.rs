@@ -3,5 +3,8 @@ pub mod corpus_crash;
 pub mod corpus_input;
 pub mod dict;
 pub mod fuzzer;
+pub mod oracle;
+pub mod reference;
+pub mod revert;
 pub mod starknet_worker;
 pub mod stats;
@@ -0,0 +1,94 @@
+use felt::Felt252;
+use std::sync::Arc;
+
+use crate::runner::runner::RunnerOutput;
+
+/// Post-execution check registered through the library API
+///
+/// Oracles are called after every successful execution and can flag
+/// executions that are wrong without failing, for example a return value
+/// out of range or an unexpected storage write.
+/// ```ignore
+/// struct MaxSupply;
+/// impl Oracle for MaxSupply {
+///     fn name(&self) -> String {
+///         "max_supply".to_string()
+///     }
+///     fn check(&self, _input: &Vec<Felt252>, output: &RunnerOutput) -> Result<(), String> {
+///         match output.retdata.first() {
+///             Some(value) if value > &Felt252::new(MAX_SUPPLY) => Err(format!("supply {}", value)),
+///             _ => Ok(()),
+///         }
+///     }
+/// }
+/// fuzzer.register_oracle(Arc::new(MaxSupply));
+/// ```
+pub trait Oracle: Send + Sync {
+    /// Name of the oracle, used in the crash reports
+    fn name(&self) -> String;
+
+    /// Check an execution of the fuzzed function with `input`
+    /// Returns the violation message if the execution is wrong
+    fn check(&self, input: &Vec<Felt252>, output: &RunnerOutput) -> Result<(), String>;
+}
+
+/// Run every oracle on a successful execution
+/// A violation is returned as an error so it is saved as a crash
+pub fn check_oracles(
+    oracles: &Vec<Arc<dyn Oracle>>,
+    input: &Vec<Felt252>,
+    result: Result<Option<RunnerOutput>, String>,
+) -> Result<Option<RunnerOutput>, String> {
+    if let Ok(Some(output)) = &result {
+        for oracle in oracles {
+            if let Err(message) = oracle.check(input, output) {
+                return Err(format!("Oracle {} violated: {}", oracle.name(), message));
+            }
+        }
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MaxValue;
+
+    impl Oracle for MaxValue {
+        fn name(&self) -> String {
+            "max_value".to_string()
+        }
+        fn check(&self, input: &Vec<Felt252>, output: &RunnerOutput) -> Result<(), String> {
+            match output.retdata.first() {
+                Some(value) if value > &input[0] => Err(format!("returned {}", value)),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn output(retdata: Vec<Felt252>) -> Result<Option<RunnerOutput>, String> {
+        Ok(Some(RunnerOutput {
+            retdata,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_check_oracles() {
+        let oracles: Vec<Arc<dyn Oracle>> = vec![Arc::new(MaxValue)];
+        let input = vec![Felt252::from(10)];
+        assert!(check_oracles(&oracles, &input, output(vec![Felt252::from(5)])).is_ok());
+        assert_eq!(
+            check_oracles(&oracles, &input, output(vec![Felt252::from(11)])).unwrap_err(),
+            "Oracle max_value violated: returned 11"
+        );
+        // Failures are not checked by the oracles
+        let error = "Error at pc=0:12:".to_string();
+        assert_eq!(
+            check_oracles(&oracles, &input, Err(error.clone())).unwrap_err(),
+            error
+        );
+        assert!(check_oracles(&Vec::new(), &input, output(vec![Felt252::from(11)])).is_ok());
+    }
+}
@@ -0,0 +1,217 @@
+use felt::Felt252;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use super::revert::{escape_error_messages, is_assume};
+use crate::json::json_parser::parse_felt;
+use crate::runner::runner::{format_felts, RunnerOutput};
+
+/// External reference implementation of the fuzzed function
+///
+/// Each input is sent to the command as a JSON line on its stdin:
+/// `{"function": "add", "inputs": ["1", "2"]}`
+/// and the command answers with a JSON line containing the expected return values:
+/// `["3"]` or `null` if the function is expected to fail
+pub struct Reference {
+    command: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Reference {
+    /// Start the reference command
+    pub fn new(command: &String) -> Self {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to start the reference command");
+        let stdin = child.stdin.take().expect("Failed to get reference stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("Failed to get reference stdout"));
+        return Reference {
+            command: command.clone(),
+            child,
+            stdin,
+            stdout,
+        };
+    }
+
+    /// Get the return values expected by the reference
+    /// `None` means that the reference expects the function to fail
+    pub fn query(
+        &mut self,
+        func_name: &String,
+        inputs: &Vec<Felt252>,
+    ) -> Result<Option<Vec<Felt252>>, String> {
+        let request = json!({
+            "function": func_name,
+            "inputs": inputs.iter().map(|input| input.to_string()).collect::<Vec<String>>(),
+        });
+        writeln!(self.stdin, "{}", request)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("Failed to send input to the reference: {}", e))?;
+        let mut line = String::new();
+        let size = self
+            .stdout
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read the reference answer: {}", e))?;
+        if size == 0 {
+            return Err("The reference command exited".to_string());
+        }
+        let answer: Value = serde_json::from_str(&line)
+            .map_err(|_| format!("Reference answer is not valid JSON: {:?}", line))?;
+        if answer.is_null() {
+            return Ok(None);
+        }
+        let mut values = Vec::<Felt252>::new();
+        for value in answer
+            .as_array()
+            .ok_or(format!("Reference answer is not an array: {}", answer))?
+        {
+            let felt = match value {
+                Value::String(value) => parse_felt(value),
+                Value::Number(value) => parse_felt(&value.to_string()),
+                _ => Err(format!("invalid felt {}", value)),
+            };
+            values.push(felt.map_err(|e| format!("Reference answer has an {}", e))?);
+        }
+        return Ok(Some(values));
+    }
+
+    /// Get the return values expected by the reference
+    /// The command is restarted once if it died or broke the protocol, the campaign
+    /// is aborted if it keeps failing since every input would be saved as a crash
+    pub fn expected(&mut self, func_name: &String, inputs: &Vec<Felt252>) -> Option<Vec<Felt252>> {
+        match self.query(func_name, inputs) {
+            Ok(expected) => return expected,
+            Err(e) => eprintln!("{}, restarting the reference command", e),
+        }
+        *self = Reference::new(&self.command);
+        match self.query(func_name, inputs) {
+            Ok(expected) => expected,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1)
+            }
+        }
+    }
+
+    /// Compare an execution with the reference
+    /// A mismatch is returned as an error so it is saved as a crash
+    pub fn check(
+        &mut self,
+        func_name: &String,
+        inputs: &Vec<Felt252>,
+        result: Result<Option<RunnerOutput>, String>,
+    ) -> Result<Option<RunnerOutput>, String> {
+        match result {
+            Ok(Some(output)) => match self.expected(func_name, inputs) {
+                Some(expected) if expected != output.retdata => Err(format!(
+                    "Reference mismatch: returned {} but the reference expects {}",
+                    format_felts(&output.retdata),
+                    format_felts(&expected)
+                )),
+                Some(_) => Ok(Some(output)),
+                None => Err(format!(
+                    "Reference mismatch: returned {} but the reference expects a failure",
+                    format_felts(&output.retdata)
+                )),
+            },
+            // The input is discarded, the reference has nothing to compare
+            Err(e) if is_assume(&e) => Err(e),
+            // The error messages are escaped so an expected revert is not filtered
+            Err(e) => match self.expected(func_name, inputs) {
+                Some(expected) => Err(format!(
+                    "Reference mismatch: the reference expects {} but the function failed: {}",
+                    format_felts(&expected),
+                    escape_error_messages(&e)
+                )),
+                None => Err(e),
+            },
+            result => result,
+        }
+    }
+}
+
+impl Drop for Reference {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(retdata: Vec<Felt252>) -> Result<Option<RunnerOutput>, String> {
+        Ok(Some(RunnerOutput {
+            retdata,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_reference_check() {
+        let func_name = "add".to_string();
+        let inputs = vec![Felt252::from(1), Felt252::from(2)];
+        let mut reference = Reference::new(&"while read line; do echo '[\"3\"]'; done".to_string());
+        assert!(reference
+            .check(&func_name, &inputs, output(vec![Felt252::from(3)]))
+            .is_ok());
+        let error = reference
+            .check(&func_name, &inputs, output(vec![Felt252::from(4)]))
+            .unwrap_err();
+        assert!(error.starts_with("Reference mismatch: returned [4]"));
+        // A failure is compared too, even an expected revert
+        let error = reference
+            .check(
+                &func_name,
+                &inputs,
+                Err("Error message: overflow\nError at pc=0:12:".to_string()),
+            )
+            .unwrap_err();
+        assert!(error.starts_with("Reference mismatch: the reference expects [3]"));
+        assert!(!error.contains("Error message: "));
+        // An assumption is not compared
+        let error = "Error message: FUZZ_ASSUME\nError at pc=0:12:".to_string();
+        assert_eq!(
+            reference
+                .check(&func_name, &inputs, Err(error.clone()))
+                .unwrap_err(),
+            error
+        );
+    }
+
+    #[test]
+    fn test_reference_expects_failure() {
+        let func_name = "add".to_string();
+        let inputs = vec![Felt252::from(1)];
+        let mut reference = Reference::new(&"while read line; do echo null; done".to_string());
+        let error = "Error at pc=0:12:".to_string();
+        assert_eq!(
+            reference
+                .check(&func_name, &inputs, Err(error.clone()))
+                .unwrap_err(),
+            error
+        );
+        assert!(reference
+            .check(&func_name, &inputs, output(vec![Felt252::from(1)]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_reference_restart() {
+        // The command exits after each answer, it is restarted instead of reporting crashes
+        let mut reference = Reference::new(&"read line; echo '[\"0x10\"]'".to_string());
+        for _ in 0..3 {
+            assert_eq!(
+                reference.expected(&"f".to_string(), &vec![Felt252::from(1)]),
+                Some(vec![Felt252::from(16)])
+            );
+        }
+    }
+}
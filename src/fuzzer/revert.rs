@@ -0,0 +1,94 @@
+/// Error message used by the property functions to discard an input
+/// ```cairo
+/// with_attr error_message("FUZZ_ASSUME") {
+///     assert_le(amount, MAX_AMOUNT);
+/// }
+/// ```
+pub const ASSUME_MESSAGE: &str = "FUZZ_ASSUME";
+
+/// Get the `with_attr` error messages of an execution error
+pub fn get_error_messages(error: &String) -> Vec<String> {
+    let mut messages = Vec::<String>::new();
+    for line in error.lines() {
+        if let Some(index) = line.find("Error message: ") {
+            messages.push(line[index + "Error message: ".len()..].trim().to_string());
+        }
+    }
+    return messages;
+}
+
+/// Check if the error was raised by an assumption of the function
+pub fn is_assume(error: &String) -> bool {
+    get_error_messages(error)
+        .iter()
+        .any(|message| message == ASSUME_MESSAGE)
+}
+
+/// Check if the error is an expected revert of the contract
+/// `patterns` are matched against the `with_attr` error messages only so the
+/// VM errors raised outside of these blocks are never filtered
+pub fn is_expected_revert(error: &String, patterns: &Vec<String>) -> bool {
+    get_error_messages(error)
+        .iter()
+        .any(|message| patterns.iter().any(|pattern| message.contains(pattern)))
+}
+
+/// Rename the `with_attr` error messages of an error reported as a crash
+/// so the workers never filter it as an assumption or an expected revert
+pub fn escape_error_messages(error: &String) -> String {
+    error.replace("Error message: ", "Reverted with: ")
+}
+
+/// Error of a differential run where only the `failed` version returned `error`
+/// An assumption of the failing version discards the input as for a single contract.
+/// Any other error is a mismatch, even an expected revert since the other version
+/// accepted the input, so its `with_attr` messages are escaped
+pub fn diff_failure(error: &String, failed: &str) -> String {
+    if is_assume(error) {
+        return error.clone();
+    }
+    return format!(
+        "Differential mismatch: only the {} failed: {}",
+        failed,
+        escape_error_messages(error)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_assume_error() {
+        let error = "Error message: FUZZ_ASSUME\nError at pc=0:12:\nAn ASSERT_EQ instruction failed: 1 != 0.".to_string();
+        assert_eq!(get_error_messages(&error), vec!["FUZZ_ASSUME"]);
+        assert!(is_assume(&error));
+        let error = "Error at pc=0:12:\nAn ASSERT_EQ instruction failed: 1 != 0.".to_string();
+        assert!(!is_assume(&error));
+    }
+    #[test]
+    fn test_expected_revert() {
+        let patterns = vec!["amount exceeds balance".to_string()];
+        let error = "Error message: ERC20: amount exceeds balance\nError at pc=0:12:".to_string();
+        assert!(is_expected_revert(&error, &patterns));
+        let error = "Error at pc=0:12:\nValue is not a range-check builtin pointer".to_string();
+        assert!(!is_expected_revert(&error, &patterns));
+    }
+    #[test]
+    fn test_diff_failure() {
+        let patterns = vec!["amount exceeds balance".to_string()];
+        // An expected revert of one version only is a regression
+        let error = "Error message: ERC20: amount exceeds balance\nError at pc=0:12:".to_string();
+        let mismatch = diff_failure(&error, "second contract");
+        assert!(mismatch.starts_with("Differential mismatch: only the second contract failed"));
+        assert!(mismatch.contains("ERC20: amount exceeds balance"));
+        assert!(!is_expected_revert(&mismatch, &patterns));
+        assert!(!is_assume(&mismatch));
+        // A crash of one version only is a mismatch
+        let error = "Error at pc=0:12:\nAn ASSERT_EQ instruction failed: 1 != 0.".to_string();
+        let mismatch = diff_failure(&error, "first program");
+        assert!(mismatch.starts_with("Differential mismatch: only the first program failed"));
+        // An assumption of the failing version discards the input
+        let error = "Error message: FUZZ_ASSUME\nError at pc=0:12:".to_string();
+        assert!(is_assume(&diff_failure(&error, "first contract")));
+    }
+}
@@ -1,11 +1,19 @@
-use crate::mutator::mutator::{EmptyDatabase, Mutator};
-use crate::runner::runner::Runner;
+use crate::mutator::mutator::Mutator;
+use crate::runner::runner::{
+    format_events, format_felts, format_messages, format_storage_writes, Runner,
+};
 use felt::Felt252;
-use starknet_rs::services::api::contract_classes::deprecated_contract_class::ContractClass;
 use std::sync::{Arc, Mutex};
 
+use super::dict::Dict;
+use super::reference::Reference;
+use super::revert::{is_assume, is_expected_revert};
 use super::stats::*;
-use super::{corpus_crash::CrashFile, corpus_input::InputFile};
+use super::{
+    corpus_crash::{CrashFile, CrashRecord},
+    corpus_input::InputFile,
+    oracle::{check_oracles, Oracle},
+};
 
 use crate::custom_rand::rng::Rng;
 use crate::json::json_parser::Function;
@@ -21,34 +29,46 @@ pub enum StarknetworkerError {
 pub struct StarknetWorker {
     stats: Arc<Mutex<Statistics>>,
     worker_id: i32,
-    contract_class: ContractClass,
+    starknet_runner: RunnerStarknet,
     function: Function,
     seed: u64,
     input_file: Arc<Mutex<InputFile>>,
     crash_file: Arc<Mutex<CrashFile>>,
     iter: i64,
+    expected_reverts: Vec<String>,
+    reference_command: String,
+    oracles: Vec<Arc<dyn Oracle>>,
+    dict: Dict,
 }
 
 impl StarknetWorker {
     pub fn new(
         stats: Arc<Mutex<Statistics>>,
         worker_id: i32,
-        contract_class: ContractClass,
+        starknet_runner: RunnerStarknet,
         function: Function,
         seed: u64,
         input_file: Arc<Mutex<InputFile>>,
         crash_file: Arc<Mutex<CrashFile>>,
         iter: i64,
+        expected_reverts: Vec<String>,
+        reference_command: String,
+        oracles: Vec<Arc<dyn Oracle>>,
+        dict: Dict,
     ) -> Self {
         StarknetWorker {
             stats,
             worker_id,
-            contract_class,
+            starknet_runner,
             function,
             seed: seed,
             input_file,
             crash_file,
             iter,
+            expected_reverts,
+            reference_command,
+            oracles,
+            dict,
         }
     }
 
@@ -60,11 +80,16 @@ impl StarknetWorker {
         // to prevent duplication of efforts
         let rng = Rng::seeded(self.seed);
 
+        // Start the reference implementation if provided
+        let mut reference = match self.reference_command.is_empty() {
+            true => None,
+            false => Some(Reference::new(&self.reference_command)),
+        };
+
         // Create a mutator
         let mut mutator = Mutator::new()
             .seed(self.seed)
             .max_input_size(self.function.num_args as usize);
-        let starknet_runner = RunnerStarknet::new(&self.contract_class);
         'next_case: loop {
             // clear previous data
             mutator.input.clear();
@@ -82,7 +107,7 @@ impl StarknetWorker {
             }
 
             // Corrupt it with 4 mutation passes
-            mutator.mutate(4, &EmptyDatabase);
+            mutator.mutate(4, &local_stats, &self.dict);
 
             // not the good size, drop this input
             if mutator.input.len() != self.function.num_args as usize {
@@ -98,12 +123,28 @@ impl StarknetWorker {
             let fuzz_input = Arc::new(mutator.input.clone());
 
             // run the cairo vm
-            match starknet_runner
+            let mut result = self
+                .starknet_runner
                 .clone()
-                .runner(&self.function.entrypoint, &mutator.input)
-            {
-                Ok(traces) => {
-                    let vec_trace = traces.expect("Could not get traces");
+                .runner(&self.function.name, &mutator.input);
+            // the reference and the oracles get the arguments without the environment
+            let args = self.starknet_runner.function_args(&mutator.input);
+            // compare the return values with the reference implementation
+            if let (Some(reference), Ok(args)) = (&mut reference, &args) {
+                result = reference.check(&self.function.name, args, result);
+            }
+            // keep the events and messages for the crash record if an oracle flags the execution
+            let (events, messages) = match &result {
+                Ok(Some(output)) => (output.events.clone(), output.messages.clone()),
+                _ => (Vec::new(), Vec::new()),
+            };
+            // check the execution with the oracles
+            if let Ok(args) = &args {
+                result = check_oracles(&self.oracles, args, result);
+            }
+            match result {
+                Ok(output) => {
+                    let vec_trace = output.expect("Could not get traces").trace;
 
                     // Mutex locking is limited to this scope
                     {
@@ -138,6 +179,10 @@ impl StarknetWorker {
                             let mut stats = self.stats.lock().expect("Failed to get mutex");
 
                             if !stats.coverage_db.contains_key(&vec_trace) {
+                                // Count the coverage found thanks to the dictionary
+                                if mutator.dict_mutations > 0 {
+                                    stats.dict_coverage += 1;
+                                }
                                 // Save input to global input database
                                 if stats.input_db.insert(fuzz_input.clone()) {
                                     // Copy in the input list
@@ -157,6 +202,18 @@ impl StarknetWorker {
                         }
                     }
                 }
+                Err(e) if is_assume(&e) => {
+                    // The input does not satisfy the assumptions of the function, discard it
+                    local_stats.discarded += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.discarded += 1;
+                }
+                Err(e) if is_expected_revert(&e, &self.expected_reverts) => {
+                    // The contract reverted on purpose, count it without saving a crash
+                    local_stats.expected_reverts += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.expected_reverts += 1;
+                }
                 Err(e) => {
                     // Mutex locking is limited to this scope
                     {
@@ -184,6 +241,16 @@ impl StarknetWorker {
                             let mut crash_file_lock =
                                 self.crash_file.lock().expect("Failed to get mutex");
                             crash_file_lock.crashes.push(fuzz_input.to_vec());
+                            crash_file_lock.records.push(CrashRecord {
+                                input: fuzz_input.to_vec(),
+                                error: e.clone(),
+                                events: events,
+                                messages: messages,
+                                storage: self
+                                    .starknet_runner
+                                    .clone()
+                                    .storage_writes(&self.function.name, &mutator.input),
+                            });
                             crash_file_lock.dump_json();
 
                             println!(
@@ -210,15 +277,44 @@ impl StarknetWorker {
     pub fn replay(&mut self, inputs: Vec<Arc<Vec<Felt252>>>) {
         // Local stats database
         let mut local_stats = Statistics::default();
-        let starknet_runner = RunnerStarknet::new(&self.contract_class);
+        // Start the reference implementation if provided
+        let mut reference = match self.reference_command.is_empty() {
+            true => None,
+            false => Some(Reference::new(&self.reference_command)),
+        };
         for input in inputs {
             let fuzz_input = input.clone();
-            match starknet_runner
+            let mut result = self
+                .starknet_runner
                 .clone()
-                .runner(&self.function.entrypoint, &fuzz_input)
-            {
-                Ok(traces) => {
-                    let vec_trace = traces.expect("Could not get traces");
+                .runner(&self.function.name, &fuzz_input);
+            // the reference and the oracles get the arguments without the environment
+            let args = self.starknet_runner.function_args(&fuzz_input);
+            // compare the return values with the reference implementation
+            if let (Some(reference), Ok(args)) = (&mut reference, &args) {
+                result = reference.check(&self.function.name, args, result);
+            }
+            // check the execution with the oracles
+            if let Ok(args) = &args {
+                result = check_oracles(&self.oracles, args, result);
+            }
+            match result {
+                Ok(output) => {
+                    let output = output.expect("Could not get traces");
+                    println!(
+                        "WORKER {} -- INPUT => {:?} -- RETURN => {}",
+                        self.worker_id,
+                        &input,
+                        format_felts(&output.retdata)
+                    );
+                    if !output.events.is_empty() {
+                        println!("    EVENTS => {}", format_events(&output.events));
+                    }
+                    if !output.messages.is_empty() {
+                        println!("    MESSAGES => {}", format_messages(&output.messages));
+                    }
+                    self.print_storage_writes(&fuzz_input);
+                    let vec_trace = output.trace;
                     // Mutex locking is limited to this scope
                     {
                         let stats = self.stats.lock().expect("Failed to get mutex");
@@ -259,6 +355,18 @@ impl StarknetWorker {
                         }
                     }
                 }
+                Err(e) if is_assume(&e) => {
+                    // The input does not satisfy the assumptions of the function, discard it
+                    local_stats.discarded += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.discarded += 1;
+                }
+                Err(e) if is_expected_revert(&e, &self.expected_reverts) => {
+                    // The contract reverted on purpose, count it without saving a crash
+                    local_stats.expected_reverts += 1;
+                    let mut stats = self.stats.lock().expect("Failed to get mutex");
+                    stats.expected_reverts += 1;
+                }
                 Err(e) => {
                     // Mutex locking is limited to this scope
                     {
@@ -282,6 +390,7 @@ impl StarknetWorker {
                                 "WORKER {} -- INPUT => {:?} -- ERROR \"{:?}\"",
                                 self.worker_id, &input, e
                             );
+                            self.print_storage_writes(&fuzz_input);
                         }
                     }
                 }
@@ -298,4 +407,15 @@ impl StarknetWorker {
         let mut stats = self.stats.lock().expect("Failed to get mutex");
         stats.threads_finished += 1;
     }
+
+    /// Print the storage written by the execution of `input`
+    fn print_storage_writes(&self, input: &Vec<Felt252>) {
+        let writes = self
+            .starknet_runner
+            .clone()
+            .storage_writes(&self.function.name, input);
+        if !writes.is_empty() {
+            println!("    STORAGE => {}", format_storage_writes(&writes));
+        }
+    }
 }
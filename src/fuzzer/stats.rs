@@ -1,3 +1,4 @@
+use crate::mutator::mutator::InputDatabase;
 use felt::Felt252;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -29,6 +30,15 @@ pub struct Statistics {
     /// Set of all unique crashes
     pub crash_db: HashSet<FuzzInput>,
 
+    /// Counter of inputs discarded by an assumption
+    pub discarded: u64,
+
+    /// Counter of expected reverts
+    pub expected_reverts: u64,
+
+    /// Counter of coverage entries found by inputs mutated with the dictionary
+    pub dict_coverage: u64,
+
     // Number of threads that finished to run
     pub threads_finished: u64,
 }
@@ -38,3 +48,39 @@ impl Statistics {
         &self.input_list[index]
     }
 }
+
+/// The shared corpus, used as donor inputs for splicing
+impl InputDatabase for Statistics {
+    fn num_inputs(&self) -> usize {
+        self.input_list.len()
+    }
+
+    fn input(&self, idx: usize) -> Option<Vec<Felt252>> {
+        self.input_list.get(idx).map(|input| input.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_database() {
+        let mut stats = Statistics::default();
+        assert_eq!(stats.num_inputs(), 0);
+        assert_eq!(stats.input(0), None);
+        let inputs = [
+            vec![Felt252::from(1), Felt252::from(2)],
+            vec![Felt252::from(3)],
+        ];
+        for input in inputs.iter() {
+            stats.input_list.push(Arc::new(input.clone()));
+        }
+        assert_eq!(stats.num_inputs(), 2);
+        assert_eq!(stats.input(0), Some(inputs[0].clone()));
+        assert_eq!(stats.input(1), Some(inputs[1].clone()));
+        assert_eq!(stats.input(2), None);
+        // Any position can be spliced
+        assert!(stats.positions(0).is_empty());
+    }
+}
@@ -1,5 +1,16 @@
+use felt::Felt252;
+use num_bigint::BigUint;
 use serde_json::Value;
 
+/// Prefix of the property testing functions
+pub const PROPTESTING_PREFIX: &str = "Fuzz_";
+/// Decorator marking a starknet function as a property
+pub const PROPTESTING_DECORATOR: &str = "fuzz";
+/// Prefix of the invariants of a starknet contract
+pub const INVARIANT_PREFIX: &str = "invariant_";
+/// Decorators of the starknet functions that can be fuzzed
+pub const ENTRYPOINT_DECORATORS: [&str; 4] = ["external", "view", "l1_handler", "constructor"];
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub name: String,
@@ -11,6 +22,41 @@ pub struct Function {
     pub _starknet: bool,
 }
 
+/// Maximum length of a Cairo short string
+const SHORT_STRING_MAX_LEN: usize = 31;
+
+/// Parse a felt written as a decimal or hexadecimal (`0x`) value lower than P,
+/// negative values are taken modulo P and quoted values are short strings
+pub fn parse_felt(value: &str) -> Result<Felt252, String> {
+    let value = value.trim();
+    if let Some(value) = value.strip_prefix("-") {
+        return parse_felt(value).map(|felt| -felt);
+    }
+    for quote in ['\'', '"'] {
+        if let Some(string) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            if !string.is_ascii() || string.len() > SHORT_STRING_MAX_LEN {
+                return Err(format!(
+                    "short string {} must have at most {} ASCII characters",
+                    value, SHORT_STRING_MAX_LEN
+                ));
+            }
+            return Ok(Felt252::from_bytes_be(string.as_bytes()));
+        }
+    }
+    let number = match value.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(value.as_bytes(), 10),
+    }
+    .ok_or(format!("invalid felt {}", value))?;
+    if number >= Felt252::prime() {
+        return Err(format!("felt {} is not lower than the field prime", value));
+    }
+    return Ok(Felt252::from_bytes_be(&number.to_bytes_be()));
+}
+
 /// Function that returns a vector of the args type of the function the user want to fuzz
 fn get_type_args(members: &Value) -> Vec<String> {
     let mut type_args = Vec::<String>::new();
@@ -85,7 +131,11 @@ pub fn parse_starknet_json(data: &String, function_name: &String) -> Option<Func
                     if let (Some(size), Some(members)) =
                         (identifiers_key.get("size"), identifiers_key.get("members"))
                     {
-                        if &name.to_string() == function_name && decorators[0] == "external" {
+                        if &name.to_string() == function_name
+                            && ENTRYPOINT_DECORATORS
+                                .iter()
+                                .any(|decorator| decorators.contains(&decorator.to_string()))
+                        {
                             return Some(Function {
                                 _starknet: starknet,
                                 entrypoint: pc,
@@ -108,16 +158,42 @@ pub fn parse_starknet_json(data: &String, function_name: &String) -> Option<Func
 }
 
 /// Function to get all property testing functions
+/// For starknet artifacts, only the external functions of the contract are returned
 pub fn get_proptesting_functions(data: &String) -> Vec<String> {
     let mut functions: Vec<String> = Vec::new();
-    let data: Value = serde_json::from_str(&data).expect("JSON was not well-formatted");
+    let mut data: Value = serde_json::from_str(&data).expect("JSON was not well-formatted");
+    let mut starknet = false;
+    if let Some(program) = data.get("program") {
+        data = program.clone();
+        starknet = true;
+    }
     if let Some(identifiers) = data.get("identifiers") {
         for (key, value) in identifiers
             .as_object()
             .expect("Failed to get identifier from json")
         {
-            let name = key.split(".").last().unwrap().to_string();
-            if value["type"] == "function" && name.contains("Fuzz_") {
+            if value["type"] != "function" {
+                continue;
+            }
+            let key_split = key.split(".").collect::<Vec<&str>>();
+            let name = key_split[key_split.len() - 1].to_string();
+            if !starknet {
+                if name.contains(PROPTESTING_PREFIX) {
+                    functions.push(name);
+                }
+                continue;
+            }
+            if key_split.len() != 2 || key_split[0] != "__main__" {
+                continue;
+            }
+            let decorators = match value.get("decorators") {
+                Some(decorators_data) => get_decorators(decorators_data),
+                None => Vec::new(),
+            };
+            if decorators.contains(&"external".to_string())
+                && (name.starts_with(PROPTESTING_PREFIX)
+                    || decorators.contains(&PROPTESTING_DECORATOR.to_string()))
+            {
                 functions.push(name);
             }
         }
@@ -125,6 +201,93 @@ pub fn get_proptesting_functions(data: &String) -> Vec<String> {
     return functions;
 }
 
+/// Function to get all the invariants of a starknet contract
+/// Invariants are `@view` functions starting with `invariant_`
+pub fn get_invariant_functions(data: &String) -> Vec<Function> {
+    let mut functions: Vec<Function> = Vec::new();
+    let json: Value = serde_json::from_str(&data).expect("JSON was not well-formatted");
+    if let Some(identifiers) = json["program"].get("identifiers") {
+        for (key, value) in identifiers
+            .as_object()
+            .expect("Failed to get identifier from json")
+        {
+            let key_split = key.split(".").collect::<Vec<&str>>();
+            if value["type"] != "function" || key_split.len() != 2 || key_split[0] != "__main__" {
+                continue;
+            }
+            let name = key_split[1].to_string();
+            let decorators = match value.get("decorators") {
+                Some(decorators_data) => get_decorators(decorators_data),
+                None => Vec::new(),
+            };
+            if name.starts_with(INVARIANT_PREFIX) && decorators.contains(&"view".to_string()) {
+                if let Some(function) = parse_starknet_json(data, &name) {
+                    functions.push(function);
+                }
+            }
+        }
+    }
+    return functions;
+}
+
+/// Function to get all the external functions of a starknet contract
+pub fn get_external_functions(data: &String) -> Vec<Function> {
+    let mut functions: Vec<Function> = Vec::new();
+    let json: Value = serde_json::from_str(&data).expect("JSON was not well-formatted");
+    if let Some(identifiers) = json["program"].get("identifiers") {
+        for (key, value) in identifiers
+            .as_object()
+            .expect("Failed to get identifier from json")
+        {
+            let key_split = key.split(".").collect::<Vec<&str>>();
+            if value["type"] != "function" || key_split.len() != 2 || key_split[0] != "__main__" {
+                continue;
+            }
+            let decorators = match value.get("decorators") {
+                Some(decorators_data) => get_decorators(decorators_data),
+                None => Vec::new(),
+            };
+            if decorators.contains(&"external".to_string()) {
+                if let Some(function) = parse_starknet_json(data, &key_split[1].to_string()) {
+                    functions.push(function);
+                }
+            }
+        }
+    }
+    return functions;
+}
+
+/// Function to get the `@storage_var` of a starknet contract as
+/// (name, number of key felts, number of felts of the value)
+pub fn get_storage_vars(data: &String) -> Vec<(String, u64, u64)> {
+    let mut storage_vars = Vec::<(String, u64, u64)>::new();
+    let json: Value = serde_json::from_str(&data).expect("JSON was not well-formatted");
+    if let Some(identifiers) = json["program"].get("identifiers") {
+        for (key, value) in identifiers
+            .as_object()
+            .expect("Failed to get identifier from json")
+        {
+            let key_split = key.split(".").collect::<Vec<&str>>();
+            // Storage variables are namespaces with an `addr` function
+            if value["type"] != "namespace" || key_split.len() != 2 || key_split[0] != "__main__" {
+                continue;
+            }
+            let size = |function: &str| {
+                identifiers
+                    .get(format!("{}.{}.Args", key, function))
+                    .and_then(|args| args.get("size"))
+                    .and_then(|size| size.as_u64())
+            };
+            if let Some(keys) = size("addr") {
+                // `write` takes the keys then the value
+                let value_size = size("write").map_or(1, |size| size.saturating_sub(keys));
+                storage_vars.push((key_split[1].to_string(), keys, value_size));
+            }
+        }
+    }
+    return storage_vars;
+}
+
 /// Function to parse cairo json artifact
 pub fn parse_json(data: &String, function_name: &String) -> Option<Function> {
     let starknet = false;
@@ -200,6 +363,83 @@ mod tests {
         }
     }
     #[test]
+    fn test_proptesting_functions() {
+        let filename = "tests/fuzzinglabs-proptesting.json";
+        let contents = fs::read_to_string(&filename.to_string())
+            .expect("Should have been able to read the file");
+        let mut functions = get_proptesting_functions(&contents);
+        functions.sort();
+        assert_eq!(functions, vec!["Fuzz_one", "Fuzz_three", "Fuzz_two"]);
+    }
+    #[test]
+    fn test_starknet_proptesting_functions() {
+        let content = r###"{
+            "program": {
+                "identifiers": {
+                    "__main__.Fuzz_balance": {"decorators": ["external"], "pc": 0, "type": "function"},
+                    "__main__.check_owner": {"decorators": ["fuzz", "external"], "pc": 10, "type": "function"},
+                    "__main__.Fuzz_internal": {"decorators": [], "pc": 20, "type": "function"},
+                    "__main__.transfer": {"decorators": ["external"], "pc": 30, "type": "function"},
+                    "__wrappers__.Fuzz_balance": {"decorators": ["external"], "pc": 40, "type": "function"}
+                }
+            }
+        }"###;
+        let mut functions = get_proptesting_functions(&content.to_string());
+        functions.sort();
+        assert_eq!(functions, vec!["Fuzz_balance", "check_owner"]);
+    }
+    #[test]
+    fn test_no_invariant_functions() {
+        let filename = "tests/fuzzinglabs-starknet.json";
+        let contents = fs::read_to_string(&filename.to_string())
+            .expect("Should have been able to read the file");
+        assert_eq!(get_invariant_functions(&contents).len(), 0);
+    }
+    #[test]
+    fn test_storage_vars() {
+        let filename = "tests/storage.json";
+        let contents = fs::read_to_string(&filename.to_string())
+            .expect("Should have been able to read the file");
+        let storage_vars = get_storage_vars(&contents);
+        assert_eq!(storage_vars, vec![("_counter".to_string(), 0, 1)]);
+    }
+
+    #[test]
+    fn test_entrypoint_decorators() {
+        let contents = fs::read_to_string("tests/storage.json")
+            .expect("Should have been able to read the file");
+        let name = "write_and_read".to_string();
+        for decorator in ["l1_handler", "constructor"] {
+            let contents = contents.replace("\"external\"", &format!("\"{}\"", decorator));
+            let function = parse_starknet_json(&contents, &name).expect("Should be parsed");
+            assert_eq!(function.decorators, vec![decorator.to_string()]);
+            assert_eq!(function.num_args, 1);
+        }
+        // Internal functions are not entrypoints
+        let contents = contents.replace("\"external\"", "\"internal\"");
+        assert!(parse_starknet_json(&contents, &name).is_none());
+    }
+
+    #[test]
+    fn test_parse_felt() {
+        assert_eq!(parse_felt("42"), Ok(Felt252::new(42)));
+        assert_eq!(parse_felt("0x2a"), Ok(Felt252::new(42)));
+        assert_eq!(parse_felt("-1"), Ok(-Felt252::new(1)));
+        assert!(parse_felt("owner").is_err());
+        assert_eq!(parse_felt("'owner'"), Ok(Felt252::from_bytes_be(b"owner")));
+        assert_eq!(parse_felt("\"f\""), Ok(Felt252::new('f' as u32)));
+        assert!(parse_felt(&format!("'{}'", "a".repeat(32))).is_err());
+        assert!(parse_felt("'owner").is_err());
+        // Values are not reduced modulo P
+        let prime = Felt252::prime();
+        assert!(parse_felt(&prime.to_string()).is_err());
+        assert!(parse_felt(&format!("0x{}", prime.to_str_radix(16))).is_err());
+        assert_eq!(
+            parse_felt(&(prime - 1u32).to_string()),
+            Ok(-Felt252::new(1))
+        );
+    }
+    #[test]
     fn test_good_json_bad_function_name() {
         let filename = "tests/fuzzinglabs.json";
         let contents = fs::read_to_string(&filename.to_string())
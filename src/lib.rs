@@ -0,0 +1,6 @@
+pub mod cli;
+pub mod custom_rand;
+pub mod fuzzer;
+pub mod json;
+pub mod mutator;
+pub mod runner;
@@ -2,16 +2,10 @@ use std::{fs, process};
 
 use clap::Parser;
 
-mod cli;
-mod custom_rand;
-mod fuzzer;
-mod json;
-mod mutator;
-mod runner;
-
-use cli::args::Opt;
-use cli::config::Config;
-use fuzzer::fuzzer::Fuzzer;
+use cairo_fuzzer::cli::args::Opt;
+use cairo_fuzzer::cli::config::Config;
+use cairo_fuzzer::fuzzer::fuzzer::Fuzzer;
+use cairo_fuzzer::json;
 
 use log::error;
 fn main() {
@@ -38,7 +32,11 @@ fn main() {
                 error!("Fuzzer needs a contract path using --contract");
                 process::exit(1);
             }
-            if opt.function.len() == 0 && opt.proptesting == false {
+            if opt.function.len() == 0
+                && opt.proptesting == false
+                && opt.erc20 == false
+                && opt.access_control == false
+            {
                 error!("Fuzzer needs a function name to fuzz using --function");
                 process::exit(1);
             }
@@ -46,6 +44,7 @@ fn main() {
             Config {
                 workspace: opt.workspace,
                 contract_file: opt.contract,
+                diff_contract_file: opt.diffcontract,
                 function_name: opt.function,
                 input_file: opt.inputfile,
                 crash_file: opt.crashfile,
@@ -60,6 +59,17 @@ fn main() {
                 minimizer: opt.minimizer,
                 proptesting: opt.proptesting,
                 iter: opt.iter,
+                reference_command: opt.reference,
+                expected_reverts: Vec::new(),
+                sequence_length: opt.sequence_length,
+                erc20: opt.erc20,
+                access_control: opt.access_control,
+                public_functions: Vec::new(),
+                fuzz_context: opt.context,
+                state_file: opt.state,
+                manifest_file: opt.manifest,
+                mocks: Vec::new(),
+                mock_artifact: String::new(),
             }
         }
     };
@@ -81,6 +91,17 @@ fn main() {
                 config.function_name, config.iter
             );
             fuzzer.fuzz();
+            // report the result of the property
+            let stats = fuzzer.stats.lock().expect("Failed to lock stats mutex");
+            if stats.crash_db.len() == 0 {
+                println!("\t\t\t\t\t\t\t=== {} === PASSED", config.function_name);
+            } else {
+                println!(
+                    "\t\t\t\t\t\t\t=== {} === FAILED ({} unique crashes)",
+                    config.function_name,
+                    stats.crash_db.len()
+                );
+            }
         }
     } else {
         // create the fuzzer
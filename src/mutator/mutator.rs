@@ -36,7 +36,7 @@ impl InputDatabase for EmptyDatabase {
     fn num_inputs(&self) -> usize {
         0
     }
-    fn input(&self, _idx: usize) -> Option<Felt252> {
+    fn input(&self, _idx: usize) -> Option<Vec<Felt252>> {
         None
     }
 }
@@ -57,7 +57,12 @@ pub trait InputDatabase {
 
     /// Get an input with a specific zero-index identifier
     /// If the `idx` is invalid or otherwise not available, this returns `None`
-    fn input(&self, idx: usize) -> Option<Felt252>;
+    fn input(&self, idx: usize) -> Option<Vec<Felt252>>;
+
+    /// Get the offsets where the input with `idx` can be used, any offset if empty
+    fn positions(&self, _idx: usize) -> Vec<usize> {
+        Vec::new()
+    }
 }
 
 /// A basic random number generator based on xorshift64 with 64-bits of state
@@ -160,6 +165,10 @@ pub struct Mutator {
 
     /// The mutations should prefer creating ASCII-printable characters
     printable: bool,
+
+    /// Number of dictionary mutations which changed the input in the last
+    /// `mutate`
+    pub dict_mutations: usize,
 }
 
 /// A byte corruption skeleton which has user-supplied corruption logic which
@@ -191,6 +200,7 @@ impl Mutator {
             accessed: Vec::new(),
             max_input_size: 1024,
             printable: false,
+            dict_mutations: 0,
             rng: Rng {
                 seed: 0x12640367f4b7ea35,
                 exp_disabled: false,
@@ -229,7 +239,13 @@ impl Mutator {
     }
 
     /// Performs standard mutation of an the input
-    pub fn mutate<T: InputDatabase>(&mut self, mutations: usize, inputs: &T) {
+    /// Dictionary tokens are taken from `dict`
+    pub fn mutate<T: InputDatabase, D: InputDatabase>(
+        &mut self,
+        mutations: usize,
+        inputs: &T,
+        dict: &D,
+    ) {
         /// List of mutation strategies which do not require an input database
         const STRATEGIES: &[fn(&mut Mutator)] = &[
             //Mutator::shrink,
@@ -251,9 +267,13 @@ impl Mutator {
             Mutator::random_overwrite,
             //Mutator::random_insert,
             Mutator::splice_overwrite,
-            //Mutator::splice_insert,
+            Mutator::splice_insert,
+            Mutator::dict_overwrite,
+            Mutator::dict_insert,
         ];
 
+        self.dict_mutations = 0;
+
         // Save the old state of the exponential random and randomly disable
         // the exponential random
         let old_exp_state = self.rng.exp_disabled;
@@ -276,8 +296,62 @@ impl Mutator {
             let splice_insert =
                 core::ptr::eq(strat as *const (), Mutator::splice_insert as *const ());
 
-            // Handle special-case mutations which need input database access
-            if splice_overwrite || splice_insert {
+            let dict_overwrite =
+                core::ptr::eq(strat as *const (), Mutator::dict_overwrite as *const ());
+            let dict_insert = core::ptr::eq(strat as *const (), Mutator::dict_insert as *const ());
+
+            // Handle special-case mutations which need dictionary access
+            if dict_overwrite || dict_insert {
+                let dictlen = dict.num_inputs();
+                if dictlen == 0 {
+                    continue;
+                }
+
+                // Select a random token
+                let idx = self.rng.rand(0, dictlen - 1);
+                if let Some(token) = dict.input(idx).and_then(|token| token.first().cloned()) {
+                    // Use the offsets of the token if it is restricted to some arguments
+                    let positions = dict.positions(idx);
+                    let restricted = !positions.is_empty();
+                    let positions: Vec<usize> = positions
+                        .into_iter()
+                        .filter(|position| *position < self.input.len())
+                        .collect();
+                    if restricted && positions.is_empty() {
+                        continue;
+                    }
+                    if dict_overwrite {
+                        // Cannot overwrite an empty input
+                        if self.input.is_empty() {
+                            continue;
+                        }
+                        let offset = match restricted {
+                            true => positions[self.rng.rand(0, positions.len() - 1)],
+                            false => self.rand_offset(),
+                        };
+                        if self.input[offset] == token {
+                            continue;
+                        }
+                        self.input[offset] = token;
+                    } else {
+                        // Insert the token and shift the next felts, the last
+                        // ones are dropped to keep the maximum input size
+                        let offset = match restricted {
+                            true => positions[self.rng.rand(0, positions.len() - 1)],
+                            false => self.rand_offset_int(true),
+                        };
+                        let old_input = self.input.clone();
+                        self.insert(offset, token);
+                        self.input.truncate(self.max_input_size);
+                        // The token is dropped with the tail of a full input
+                        if self.input == old_input {
+                            continue;
+                        }
+                    }
+                    self.dict_mutations += 1;
+                }
+            } else if splice_overwrite || splice_insert {
+                // Handle special-case mutations which need input database access
                 // Get the number of inputs in the database
                 let dblen = inputs.num_inputs();
                 if dblen == 0 {
@@ -287,14 +361,14 @@ impl Mutator {
                 // Select a random input
                 if let Some(inp) = inputs.input(self.rng.rand(0, dblen - 1)) {
                     // Nothing to splice for an empty input
-                    /*                     if inp.is_empty() {
+                    if inp.is_empty() {
                         continue;
-                    } */
-                    println!("Inputs selected => {:?}", inp);
+                    }
+
                     // Pick a random offset and length from the input which
                     // we want to use for splicing
-                    let donor_offset = self.rng.rand_exp(0, inp.to_string().len() - 1);
-                    let donor_length = self.rng.rand_exp(1, inp.to_string().len() - donor_offset);
+                    let donor_offset = self.rng.rand_exp(0, inp.len() - 1);
+                    let donor_length = self.rng.rand_exp(1, inp.len() - donor_offset);
 
                     if splice_overwrite {
                         // Cannot overwrite an empty input
@@ -306,21 +380,21 @@ impl Mutator {
                         let offset = self.rand_offset();
                         let length = core::cmp::min(donor_length, self.input.len() - offset);
 
-                        println!("inputs to bytes => {:?}", &inp.to_be_bytes());
-                        println!(
-                            "inputs slices => {:?}",
-                            &inp.to_be_bytes()[donor_offset..donor_offset + length]
-                        );
                         // Overwrite it!
-                        self.overwrite(
-                            offset,
-                            &inp.to_be_bytes()[donor_offset..donor_offset + length],
-                        );
+                        self.input[offset..offset + length]
+                            .clone_from_slice(&inp[donor_offset..donor_offset + length]);
                     } else {
                         // Find an offset to insert at in our input
                         let offset = self.rand_offset_int(true);
-                        // Insert!
-                        self.insert(offset, inp);
+                        // Insert! The felts shifted past the maximum input
+                        // size are dropped
+                        self.input.splice(
+                            offset..offset,
+                            inp[donor_offset..donor_offset + donor_length]
+                                .iter()
+                                .cloned(),
+                        );
+                        self.input.truncate(self.max_input_size);
                     }
                 }
             } else {
@@ -378,6 +452,12 @@ impl Mutator {
     /// Dummy function, just used for RNG selection, logic is done in `mutate`
     fn splice_insert(&mut self) {}
 
+    /// Dummy function, just used for RNG selection, logic is done in `mutate`
+    fn dict_overwrite(&mut self) {}
+
+    /// Dummy function, just used for RNG selection, logic is done in `mutate`
+    fn dict_insert(&mut self) {}
+
     /// Randomly delete a chunk of the input
     fn shrink(&mut self) {
         // Nothing to do on an empty input
@@ -958,3 +1038,95 @@ impl Mutator {
     // Negate a byte in the input
     byte_corruptor!(neg_byte, |_: &mut Self, x: Felt252| -> Felt252 { -x });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tokens(Vec<Felt252>);
+
+    impl InputDatabase for Tokens {
+        fn num_inputs(&self) -> usize {
+            self.0.len()
+        }
+        fn input(&self, idx: usize) -> Option<Vec<Felt252>> {
+            self.0.get(idx).map(|token| vec![token.clone()])
+        }
+    }
+
+    struct Corpus(Vec<Vec<Felt252>>);
+
+    impl InputDatabase for Corpus {
+        fn num_inputs(&self) -> usize {
+            self.0.len()
+        }
+        fn input(&self, idx: usize) -> Option<Vec<Felt252>> {
+            self.0.get(idx).cloned()
+        }
+    }
+
+    fn full_input() -> Vec<Felt252> {
+        vec![Felt252::from(7), Felt252::from(7)]
+    }
+
+    #[test]
+    fn test_dict_mutations_unchanged_input() {
+        // The token is already everywhere and an insert is truncated away
+        let dict = Tokens(vec![Felt252::from(7)]);
+        for seed in 0..1000 {
+            let mut mutator = Mutator::new().seed(seed).max_input_size(2);
+            mutator.input = full_input();
+            mutator.mutate(1, &EmptyDatabase, &dict);
+            assert_eq!(mutator.dict_mutations, 0);
+        }
+    }
+
+    #[test]
+    fn test_dict_mutations_changed_input() {
+        let dict = Tokens(vec![Felt252::from(1000)]);
+        let mut counted = 0;
+        for seed in 0..1000 {
+            let mut mutator = Mutator::new().seed(seed).max_input_size(2);
+            mutator.input = full_input();
+            mutator.mutate(1, &EmptyDatabase, &dict);
+            if mutator.dict_mutations > 0 {
+                assert_ne!(mutator.input, full_input());
+                counted += 1;
+            }
+        }
+        assert!(counted > 0);
+    }
+
+    #[test]
+    fn test_splice_input_size() {
+        let donor: Vec<Felt252> = (100..110).map(Felt252::from).collect();
+        let corpus = Corpus(vec![donor.clone()]);
+        let mut spliced = 0;
+        for seed in 0..1000 {
+            let mut mutator = Mutator::new().seed(seed).max_input_size(4);
+            mutator.input = vec![Felt252::from(7); 4];
+            mutator.mutate(1, &corpus, &EmptyDatabase);
+            // Overwrites keep the size and inserts drop the shifted felts
+            assert_eq!(mutator.input.len(), 4);
+            if mutator
+                .input
+                .windows(2)
+                .any(|felts| donor.windows(2).any(|d| d == felts))
+            {
+                spliced += 1;
+            }
+        }
+        assert!(spliced > 0);
+    }
+
+    #[test]
+    fn test_splice_insert_max_input_size() {
+        let corpus = Corpus(vec![(100..110).map(Felt252::from).collect()]);
+        for seed in 0..1000 {
+            let mut mutator = Mutator::new().seed(seed).max_input_size(4);
+            mutator.input = vec![Felt252::from(7); 2];
+            mutator.mutate(4, &corpus, &EmptyDatabase);
+            assert!(mutator.input.len() >= 2 && mutator.input.len() <= 4);
+        }
+    }
+}
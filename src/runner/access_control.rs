@@ -0,0 +1,249 @@
+use felt::Felt252;
+use num_traits::{ToPrimitive, Zero};
+
+use super::runner::RunnerOutput;
+use super::starknet_runner::{RunnerStarknet, SequenceCall, StarknetState};
+use super::storage_decoder::StorageDecoder;
+use crate::json::json_parser::Function;
+
+/// Address of the deployer of the contract, always privileged
+pub const DEPLOYER_ADDRESS: u64 = 0x1000;
+
+/// Accounts used as non-privileged callers
+pub const ACCESS_CONTROL_ACCOUNTS: [u64; 3] = [0x2001, 0x2002, 0x2003];
+
+/// Detector of the state-changing functions that succeed for arbitrary callers
+/// Each call is encoded in the input as `[function index, caller index, args...]`
+#[derive(Debug, Clone)]
+pub struct AccessControl {
+    /// External functions called in the sequences
+    pub functions: Vec<Function>,
+    /// Names of the storage variables of the contract
+    pub decoder: StorageDecoder,
+    /// Storage variables holding the owner of the contract
+    pub owner_vars: Vec<String>,
+    /// Functions expected to be callable by anyone
+    pub public_functions: Vec<String>,
+    /// Number of calls of a sequence
+    pub length: usize,
+}
+
+impl AccessControl {
+    /// Create the detector, the owner is stored in the single-slot storage
+    /// variables whose name contains `owner` or `admin`
+    pub fn new(
+        functions: &Vec<Function>,
+        decoder: &StorageDecoder,
+        public_functions: &Vec<String>,
+        length: usize,
+    ) -> Self {
+        let owner_vars = decoder
+            .storage_vars
+            .iter()
+            .filter(|(name, keys, size)| {
+                let name = name.to_lowercase();
+                *keys == 0 && *size == 1 && (name.contains("owner") || name.contains("admin"))
+            })
+            .map(|(name, _, _)| name.clone())
+            .collect();
+        return AccessControl {
+            functions: functions.clone(),
+            decoder: decoder.clone(),
+            owner_vars: owner_vars,
+            public_functions: public_functions.clone(),
+            length: length,
+        };
+    }
+
+    /// Number of felts used to encode one call
+    pub fn call_size(&self) -> usize {
+        2 + self
+            .functions
+            .iter()
+            .map(|function| function.num_args as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of felts used to encode the whole sequence
+    pub fn input_size(&self) -> usize {
+        self.length * self.call_size()
+    }
+
+    /// Decode the calls of the sequence as (caller, function, calldata)
+    fn decode(&self, data: &Vec<Felt252>) -> Vec<SequenceCall> {
+        let mut callers = vec![Felt252::from(DEPLOYER_ADDRESS)];
+        callers.extend(ACCESS_CONTROL_ACCOUNTS.iter().map(|a| Felt252::from(*a)));
+        let mut calls = Vec::new();
+        for chunk in data.chunks_exact(self.call_size()) {
+            let index = (chunk[0].to_biguint() % self.functions.len())
+                .to_usize()
+                .unwrap_or(0);
+            let caller = (chunk[1].to_biguint() % callers.len())
+                .to_usize()
+                .unwrap_or(0);
+            let function = &self.functions[index];
+            let args = chunk[2..].iter().take(function.num_args as usize).cloned();
+            calls.push(SequenceCall {
+                caller: Some(callers[caller].clone()),
+                function: function.name.clone(),
+                calldata: args.collect(),
+            });
+        }
+        return calls;
+    }
+
+    /// Execute the calls encoded in `data` on `state`
+    /// A call is reported if it writes storage from a caller that is neither the deployer nor the owner
+    pub fn run(
+        &self,
+        runner: &RunnerStarknet,
+        state: &mut StarknetState,
+        data: &Vec<Felt252>,
+    ) -> Result<RunnerOutput, String> {
+        let calls = self.decode(data);
+        let deployer = Felt252::from(DEPLOYER_ADDRESS);
+        return runner.execute_sequence(state, &calls, |index, _, state, call_state| {
+            let call = &calls[index];
+            let caller = call.caller.clone().unwrap_or(deployer.clone());
+            if caller == deployer || self.public_functions.contains(&call.function) {
+                return Ok(());
+            }
+            // Owner set before this call
+            let owner = self
+                .owner_vars
+                .iter()
+                .filter_map(|name| self.decoder.key(name, &[]).ok())
+                .map(|key| runner.read_storage(state, &key))
+                .find(|value| !value.is_zero());
+            let owner = match owner {
+                Some(owner) if owner != caller => owner,
+                _ => return Ok(()),
+            };
+            // Map keys are usually arguments or the caller
+            let mut decoder = self.decoder.clone();
+            let mut candidates = call.calldata.clone();
+            candidates.push(caller.clone());
+            decoder.add_candidates(&candidates);
+            let storage = runner.get_contract_storage(state);
+            let modified: Vec<String> = runner
+                .get_contract_storage(call_state)
+                .iter()
+                .filter(|(key, value)| storage.get(key) != Some(value))
+                .map(|(key, _)| decoder.name(key))
+                .collect();
+            if modified.len() > 0 {
+                return Err(format!(
+                    "Access control: {} succeeded from {} while the owner is {} and modified {}",
+                    call.function,
+                    caller,
+                    owner,
+                    modified.join(", ")
+                ));
+            }
+            return Ok(());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::json_parser::get_external_functions;
+    use crate::runner::snapshot::{Snapshot, SnapshotEntry};
+    use starknet_rs::services::api::contract_classes::deprecated_contract_class::ContractClass;
+    use std::fs;
+    use std::str::FromStr;
+
+    fn function(name: &str, num_args: u64) -> Function {
+        Function {
+            name: name.to_string(),
+            entrypoint: "0".to_string(),
+            num_args: num_args,
+            type_args: vec!["felt".to_string(); num_args as usize],
+            hints: false,
+            decorators: vec!["external".to_string()],
+            _starknet: true,
+        }
+    }
+
+    fn felts(values: &[u64]) -> Vec<Felt252> {
+        values.iter().map(|value| Felt252::from(*value)).collect()
+    }
+
+    #[test]
+    fn test_owner_vars() {
+        let decoder = StorageDecoder::new(&vec![
+            ("_owner".to_string(), 0, 1),
+            ("Ownable_owner".to_string(), 0, 1),
+            ("ADMIN".to_string(), 0, 1),
+            ("_fee".to_string(), 0, 1),
+            // Maps and values of several felts do not hold an address
+            ("admins".to_string(), 1, 1),
+            ("owner_info".to_string(), 0, 2),
+        ]);
+        let access_control = AccessControl::new(&Vec::new(), &decoder, &Vec::new(), 2);
+        assert_eq!(
+            access_control.owner_vars,
+            vec!["_owner", "Ownable_owner", "ADMIN"]
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let functions = vec![function("set_fee", 1), function("transfer", 2)];
+        let access_control =
+            AccessControl::new(&functions, &StorageDecoder::default(), &Vec::new(), 2);
+        assert_eq!(access_control.call_size(), 4);
+        assert_eq!(access_control.input_size(), 8);
+        // The last incomplete call is ignored
+        let calls = access_control.decode(&felts(&[1, 2, 7, 8, 2, 4, 9, 10, 1]));
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function, "transfer");
+        assert_eq!(
+            calls[0].caller,
+            Some(Felt252::from(ACCESS_CONTROL_ACCOUNTS[1]))
+        );
+        assert_eq!(calls[0].calldata, felts(&[7, 8]));
+        // The indexes wrap around and the extra arguments are dropped
+        assert_eq!(calls[1].function, "set_fee");
+        assert_eq!(calls[1].caller, Some(Felt252::from(DEPLOYER_ADDRESS)));
+        assert_eq!(calls[1].calldata, felts(&[9]));
+    }
+
+    #[test]
+    fn test_run() {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let contract_class =
+            ContractClass::from_str(contents.as_str()).expect("could not get contractclass");
+        let decoder = StorageDecoder::from_artifact(&contents);
+        // The first account is the owner stored in `_counter`
+        let owner = SnapshotEntry {
+            address: None,
+            key: decoder.key("_counter", &[]).unwrap(),
+            value: Felt252::from(ACCESS_CONTROL_ACCOUNTS[0]),
+        };
+        let runner = RunnerStarknet::new(&contract_class).snapshot(&Snapshot {
+            entries: vec![owner],
+        });
+        let mut access_control =
+            AccessControl::new(&get_external_functions(&contents), &decoder, &Vec::new(), 1);
+        access_control.owner_vars = vec!["_counter".to_string()];
+        let run = |access_control: &AccessControl, caller: u64| {
+            let mut state = runner.init_state().unwrap();
+            access_control.run(&runner, &mut state, &felts(&[0, caller, 100]))
+        };
+        // Another account writes the storage
+        let error = run(&access_control, 2).err().unwrap();
+        assert!(error.starts_with(&format!(
+            "Access control: write_and_read succeeded from {} while the owner is {} and modified _counter",
+            ACCESS_CONTROL_ACCOUNTS[1], ACCESS_CONTROL_ACCOUNTS[0]
+        )));
+        // The deployer and the owner are privileged
+        assert!(run(&access_control, 0).is_ok());
+        assert!(run(&access_control, 1).is_ok());
+        // Anyone may call a public function
+        access_control.public_functions = vec!["write_and_read".to_string()];
+        assert!(run(&access_control, 2).is_ok());
+    }
+}
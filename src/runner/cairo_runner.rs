@@ -5,27 +5,47 @@ use cairo_rs::vm::runners::cairo_runner::CairoRunner;
 use cairo_rs::vm::vm_core::VirtualMachine;
 
 use felt::Felt252;
+use std::collections::HashMap;
 
-use super::runner::Runner;
+use super::runner::{format_felts, Runner, RunnerOutput};
+use crate::fuzzer::revert::diff_failure;
 #[derive(Clone)]
 pub struct RunnerCairo {
     program: Program,
+    /// Other version of the program executed on the same inputs
+    diff_runner: Option<Box<RunnerCairo>>,
 }
 
 impl RunnerCairo {
     pub fn new(program: &Program) -> Self {
         return RunnerCairo {
             program: program.clone(),
+            diff_runner: None,
         };
     }
-}
 
-impl Runner for RunnerCairo {
-    fn runner(
-        self,
-        func_name: &String,
-        data: &Vec<Felt252>,
-    ) -> Result<Option<Vec<(u32, u32)>>, String> {
+    /// Set the other version of the program used for differential fuzzing
+    pub fn differential(mut self, diff_runner: &RunnerCairo) -> Self {
+        self.diff_runner = Some(Box::new(diff_runner.clone()));
+        self
+    }
+
+    /// Get the number of felts returned by `func_name`
+    fn get_return_size(&self, func_name: &String) -> usize {
+        match self
+            .program
+            .get_identifier(&format!("__main__.{}.Return", &func_name))
+        {
+            Some(identifier) => match &identifier.cairo_type {
+                Some(cairo_type) => get_type_size(&self.program, cairo_type),
+                None => 0,
+            },
+            None => 0,
+        }
+    }
+
+    /// Execute `func_name` with `data` as arguments
+    fn run(&self, func_name: &String, data: &Vec<Felt252>) -> Result<Option<RunnerOutput>, String> {
         // Init the cairo_runner, the VM and the hint_processor
         let mut cairo_runner = CairoRunner::new(&self.program, "small", false)
             .expect("Failed to init the CairoRunner");
@@ -79,6 +99,19 @@ impl Runner for RunnerCairo {
             Ok(()) => (),
             Err(e) => return Err(e.to_string()),
         };
+        // The return values are the last values pushed by the function
+        let retdata: Vec<Felt252> = vm
+            .get_return_values(self.get_return_size(func_name))
+            .expect("Failed to get return values")
+            .iter()
+            .filter_map(|value| match value {
+                MaybeRelocatable::Int(value) => Some(value.clone()),
+                MaybeRelocatable::RelocatableValue(_) => None,
+            })
+            .collect();
+        let resources = cairo_runner
+            .get_execution_resources(&vm)
+            .expect("Failed to get execution resources");
         cairo_runner
             .relocate(&mut vm, false)
             .expect("Failed to relocate VM");
@@ -92,6 +125,121 @@ impl Runner for RunnerCairo {
                     .expect("Failed to transform offset into u32"),
             ))
         }
-        return Ok(Some(ret));
+        return Ok(Some(RunnerOutput {
+            trace: ret,
+            retdata: retdata,
+            resources: resources,
+            storage: HashMap::new(),
+            events: Vec::new(),
+            messages: Vec::new(),
+        }));
+    }
+}
+
+/// Get the number of felts of a cairo type such as `felt`, `(res: felt)` or a struct
+fn get_type_size(program: &Program, cairo_type: &String) -> usize {
+    let cairo_type = cairo_type.trim();
+    if cairo_type == "felt" || cairo_type.ends_with("*") {
+        return 1;
+    }
+    // Tuple of (named) members
+    if cairo_type.starts_with("(") && cairo_type.ends_with(")") {
+        let mut size = 0;
+        let mut depth = 0;
+        let mut member = String::new();
+        for c in cairo_type[1..cairo_type.len() - 1]
+            .chars()
+            .chain(",".chars())
+        {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => (),
+            }
+            if c == ',' && depth == 0 {
+                let member_type = match member.find(":") {
+                    Some(index) => member[index + 1..].to_string(),
+                    None => member.clone(),
+                };
+                if !member_type.trim().is_empty() {
+                    size += get_type_size(program, &member_type);
+                }
+                member.clear();
+            } else {
+                member.push(c);
+            }
+        }
+        return size;
+    }
+    // Struct, the size is the sum of its members
+    match program.get_identifier(&cairo_type.to_string()) {
+        Some(identifier) => match &identifier.members {
+            Some(members) => members
+                .values()
+                .map(|member| get_type_size(program, &member.cairo_type))
+                .sum(),
+            None => 1,
+        },
+        None => 1,
+    }
+}
+
+impl Runner for RunnerCairo {
+    fn runner(
+        self,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<Option<RunnerOutput>, String> {
+        let result = self.run(func_name, data);
+        if let Some(diff_runner) = &self.diff_runner {
+            match (&result, diff_runner.run(func_name, data)) {
+                (Ok(Some(output)), Ok(Some(diff_output)))
+                    if output.retdata != diff_output.retdata =>
+                {
+                    return Err(format!(
+                        "Differential mismatch: return values {} != {}",
+                        format_felts(&output.retdata),
+                        format_felts(&diff_output.retdata)
+                    ))
+                }
+                (Ok(_), Err(e)) => return Err(diff_failure(&e, "second program")),
+                (Err(e), Ok(_)) => return Err(diff_failure(e, "first program")),
+                _ => (),
+            }
+        }
+        return result;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::fs;
+    #[test]
+    fn test_return_size() {
+        let contents =
+            fs::read_to_string("tests/storage.json").expect("Failed to read the contract");
+        let data: Value = serde_json::from_str(&contents).expect("Failed to parse the contract");
+        let program = Program::from_bytes(data["program"].to_string().as_bytes(), None)
+            .expect("Failed to load the program");
+        assert_eq!(get_type_size(&program, &"felt".to_string()), 1);
+        assert_eq!(get_type_size(&program, &"felt*".to_string()), 1);
+        assert_eq!(get_type_size(&program, &"()".to_string()), 0);
+        assert_eq!(
+            get_type_size(&program, &"(data_len: felt, data: felt*)".to_string()),
+            2
+        );
+        // A struct is the sum of its members, nested in a tuple
+        let hash_builtin = "starkware.cairo.common.cairo_builtins.HashBuiltin".to_string();
+        assert_eq!(get_type_size(&program, &hash_builtin), 3);
+        assert_eq!(
+            get_type_size(&program, &format!("(a: felt, b: ({}, felt))", hash_builtin)),
+            5
+        );
+        let runner = RunnerCairo::new(&program);
+        assert_eq!(runner.get_return_size(&"write_and_read".to_string()), 1);
+        assert_eq!(runner.get_return_size(&"_counter.write".to_string()), 0);
+        assert_eq!(runner.get_return_size(&"unknown".to_string()), 0);
     }
 }
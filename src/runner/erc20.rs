@@ -0,0 +1,663 @@
+use felt::Felt252;
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::runner::RunnerOutput;
+use super::starknet_runner::{RunnerStarknet, SequenceCall, StarknetState};
+
+/// Accounts used as callers of the token
+pub const ERC20_ACCOUNTS: [u64; 3] = [0x1001, 0x1002, 0x1003];
+
+/// Number of felts used to encode one call: `[action, caller, target, other, amount]`
+pub const ERC20_CALL_SIZE: usize = 5;
+
+/// Entrypoints of an ERC20 token found in the ABI
+#[derive(Debug, Clone)]
+pub struct Erc20 {
+    pub total_supply: String,
+    pub balance_of: String,
+    pub allowance: Option<String>,
+    pub transfer: String,
+    pub transfer_from: Option<String>,
+    pub approve: Option<String>,
+    pub mint: Option<String>,
+    /// Amounts are `Uint256` instead of `felt`
+    pub uint256: bool,
+    /// Number of calls of a sequence
+    pub length: usize,
+}
+
+/// Call of the token decoded from the input
+#[derive(Debug, Clone)]
+enum Erc20Call {
+    Transfer {
+        recipient: Felt252,
+        amount: Felt252,
+    },
+    TransferFrom {
+        sender: Felt252,
+        recipient: Felt252,
+        amount: Felt252,
+    },
+    Approve {
+        spender: Felt252,
+        amount: Felt252,
+    },
+    Mint {
+        recipient: Felt252,
+        amount: Felt252,
+    },
+}
+
+/// Values read on the token before and after a call
+struct Erc20Snapshot {
+    total_supply: BigUint,
+    balances: HashMap<Felt252, BigUint>,
+    allowances: HashMap<(Felt252, Felt252), BigUint>,
+}
+
+/// Find an ABI function with one of the `names` taking `num_inputs` inputs
+fn find_abi_function(abi: &Vec<Value>, names: &[&str], num_inputs: usize) -> Option<Value> {
+    abi.iter()
+        .find(|entry| {
+            entry["type"] == "function"
+                && names.iter().any(|name| entry["name"] == *name)
+                && entry["inputs"].as_array().map(|inputs| inputs.len()) == Some(num_inputs)
+        })
+        .cloned()
+}
+
+impl Erc20 {
+    /// Detect the standard ERC20 entrypoints (camelCase or snake_case) in the ABI of the contract
+    pub fn detect(data: &String, length: usize) -> Option<Self> {
+        let json: Value = serde_json::from_str(&data).expect("JSON was not well-formatted");
+        let abi = json["abi"].as_array()?;
+        let name = |entry: Option<Value>| -> Option<String> {
+            entry.map(|entry| entry["name"].as_str().unwrap_or_default().to_string())
+        };
+        let transfer = find_abi_function(abi, &["transfer"], 2)?;
+        let uint256 = transfer["inputs"][1]["type"] == "Uint256";
+        return Some(Erc20 {
+            total_supply: name(find_abi_function(abi, &["totalSupply", "total_supply"], 0))?,
+            balance_of: name(find_abi_function(abi, &["balanceOf", "balance_of"], 1))?,
+            allowance: name(find_abi_function(abi, &["allowance"], 2)),
+            transfer: name(Some(transfer))?,
+            transfer_from: name(find_abi_function(
+                abi,
+                &["transferFrom", "transfer_from"],
+                3,
+            )),
+            approve: name(find_abi_function(abi, &["approve"], 2)),
+            mint: name(find_abi_function(abi, &["mint"], 2)),
+            uint256: uint256,
+            length: length,
+        });
+    }
+
+    /// Number of felts used to encode the whole sequence
+    pub fn input_size(&self) -> usize {
+        self.length * ERC20_CALL_SIZE
+    }
+
+    /// Calls available on the token
+    fn actions(&self) -> Vec<&str> {
+        let mut actions = vec!["transfer"];
+        if self.transfer_from.is_some() && self.allowance.is_some() {
+            actions.push("transfer_from");
+        }
+        if self.approve.is_some() {
+            actions.push("approve");
+        }
+        if self.mint.is_some() {
+            actions.push("mint");
+        }
+        return actions;
+    }
+
+    /// Decode the calls of the sequence as (caller, call)
+    fn decode(&self, data: &Vec<Felt252>) -> Vec<(Felt252, Erc20Call)> {
+        let actions = self.actions();
+        // The zero address is only used as a target, never as a caller
+        let mut targets: Vec<Felt252> = ERC20_ACCOUNTS.iter().map(|a| Felt252::from(*a)).collect();
+        targets.push(Felt252::zero());
+        let pick = |value: &Felt252, values: &Vec<Felt252>| -> Felt252 {
+            let index = (value.to_biguint() % values.len()).to_usize().unwrap_or(0);
+            values[index].clone()
+        };
+        let mut calls = Vec::new();
+        for chunk in data.chunks_exact(ERC20_CALL_SIZE) {
+            let action = (chunk[0].to_biguint() % actions.len())
+                .to_usize()
+                .unwrap_or(0);
+            let caller = pick(&chunk[1], &targets[..ERC20_ACCOUNTS.len()].to_vec());
+            let target = pick(&chunk[2], &targets);
+            let amount = chunk[4].clone();
+            let call = match actions[action] {
+                "transfer" => Erc20Call::Transfer {
+                    recipient: target,
+                    amount: amount,
+                },
+                "transfer_from" => Erc20Call::TransferFrom {
+                    sender: pick(&chunk[3], &targets),
+                    recipient: target,
+                    amount: amount,
+                },
+                "approve" => Erc20Call::Approve {
+                    spender: target,
+                    amount: amount,
+                },
+                _ => Erc20Call::Mint {
+                    recipient: target,
+                    amount: amount,
+                },
+            };
+            calls.push((caller, call));
+        }
+        return calls;
+    }
+
+    /// Encode an amount as calldata
+    fn encode_amount(&self, amount: &Felt252) -> Vec<Felt252> {
+        if !self.uint256 {
+            return vec![amount.clone()];
+        }
+        let value = amount.to_biguint();
+        let low = value.clone() % (BigUint::one() << 128);
+        return vec![Felt252::from(low), Felt252::from(value >> 128)];
+    }
+
+    /// Decode an amount from the return values
+    fn decode_amount(&self, retdata: &Vec<Felt252>) -> BigUint {
+        let low = retdata
+            .get(0)
+            .cloned()
+            .unwrap_or(Felt252::zero())
+            .to_biguint();
+        if !self.uint256 {
+            return low;
+        }
+        let high = retdata
+            .get(1)
+            .cloned()
+            .unwrap_or(Felt252::zero())
+            .to_biguint();
+        return low + (high << 128);
+    }
+
+    /// Function name and calldata of a call
+    fn calldata(&self, call: &Erc20Call) -> (String, Vec<Felt252>) {
+        let (name, mut calldata, amount) = match call {
+            Erc20Call::Transfer { recipient, amount } => {
+                (self.transfer.clone(), vec![recipient.clone()], amount)
+            }
+            Erc20Call::TransferFrom {
+                sender,
+                recipient,
+                amount,
+            } => (
+                self.transfer_from.clone().unwrap_or_default(),
+                vec![sender.clone(), recipient.clone()],
+                amount,
+            ),
+            Erc20Call::Approve { spender, amount } => (
+                self.approve.clone().unwrap_or_default(),
+                vec![spender.clone()],
+                amount,
+            ),
+            Erc20Call::Mint { recipient, amount } => (
+                self.mint.clone().unwrap_or_default(),
+                vec![recipient.clone()],
+                amount,
+            ),
+        };
+        calldata.extend(self.encode_amount(amount));
+        return (name, calldata);
+    }
+
+    /// Call a view function of the token
+    fn read(
+        &self,
+        runner: &RunnerStarknet,
+        state: &mut StarknetState,
+        func_name: &String,
+        args: Vec<Felt252>,
+    ) -> Result<BigUint, String> {
+        let call_info = runner
+            .execute(state, func_name, &args)
+            .map_err(|e| format!("ERC20 view {} failed: {}", func_name, e))?;
+        return Ok(self.decode_amount(&call_info.retdata));
+    }
+
+    /// Read the supply, the balances and the allowances of the accounts
+    fn snapshot(
+        &self,
+        runner: &RunnerStarknet,
+        state: &mut StarknetState,
+    ) -> Result<Erc20Snapshot, String> {
+        let mut accounts: Vec<Felt252> = ERC20_ACCOUNTS.iter().map(|a| Felt252::from(*a)).collect();
+        accounts.push(Felt252::zero());
+        let mut snapshot = Erc20Snapshot {
+            total_supply: self.read(runner, state, &self.total_supply, vec![])?,
+            balances: HashMap::new(),
+            allowances: HashMap::new(),
+        };
+        for owner in &accounts {
+            let balance = self.read(runner, state, &self.balance_of, vec![owner.clone()])?;
+            snapshot.balances.insert(owner.clone(), balance);
+            if let Some(allowance) = &self.allowance {
+                for spender in &accounts {
+                    let value = self.read(
+                        runner,
+                        state,
+                        allowance,
+                        vec![owner.clone(), spender.clone()],
+                    )?;
+                    snapshot
+                        .allowances
+                        .insert((owner.clone(), spender.clone()), value);
+                }
+            }
+        }
+        return Ok(snapshot);
+    }
+
+    /// Check the properties of a call that succeeded
+    fn check_call(
+        &self,
+        caller: &Felt252,
+        call: &Erc20Call,
+        before: &Erc20Snapshot,
+        after: &Erc20Snapshot,
+    ) -> Result<(), String> {
+        let zero = Felt252::zero();
+        // Balances moved by the call as (sender, recipient, amount), the sender of a mint is None
+        let (sender, recipient, amount) = match call {
+            Erc20Call::Transfer { recipient, amount } => (Some(caller), recipient, amount),
+            Erc20Call::TransferFrom {
+                sender,
+                recipient,
+                amount,
+            } => (Some(sender), recipient, amount),
+            Erc20Call::Approve { spender, .. } => (None, spender, &zero),
+            Erc20Call::Mint { recipient, amount } => (None, recipient, amount),
+        };
+        let amount = self.decode_amount(&self.encode_amount(amount));
+
+        // Calls involving the zero address must fail
+        if recipient == &zero || sender == Some(&zero) {
+            return Err("zero_address: call with the zero address succeeded".to_string());
+        }
+
+        // Only a mint changes the total supply
+        let mut expected_supply = before.total_supply.clone();
+        if let Erc20Call::Mint { .. } = call {
+            expected_supply += &amount;
+        }
+        if after.total_supply != expected_supply {
+            return Err(format!(
+                "total_supply: supply {} != {} expected",
+                after.total_supply, expected_supply
+            ));
+        }
+
+        // Transfers move exactly the amount between the two balances
+        for (account, balance) in &after.balances {
+            let mut expected = before.balances[account].clone();
+            if Some(account) == sender && account != recipient {
+                if expected < amount {
+                    return Err(format!(
+                        "transfer_balance: {} transferred {} with a balance of {}",
+                        account, amount, expected
+                    ));
+                }
+                expected -= &amount;
+            }
+            if account == recipient && Some(account) != sender {
+                expected += &amount;
+            }
+            if balance != &expected {
+                return Err(format!(
+                    "transfer_balance: balance of {} = {} != {} expected",
+                    account, balance, expected
+                ));
+            }
+        }
+
+        // Balances never exceed the total supply
+        let sum: BigUint = after.balances.values().sum();
+        if sum > after.total_supply {
+            return Err(format!(
+                "balance_supply: sum of the balances {} > supply {}",
+                sum, after.total_supply
+            ));
+        }
+
+        // transferFrom spends the allowance given to the caller
+        if let Erc20Call::TransferFrom { sender, .. } = call {
+            let key = (sender.clone(), caller.clone());
+            let allowance = before.allowances[&key].clone();
+            if allowance < amount {
+                return Err(format!(
+                    "transfer_from_allowance: {} transferred {} from {} with an allowance of {}",
+                    caller, amount, sender, allowance
+                ));
+            }
+            // An allowance set to the maximal value is never spent
+            let unlimited = match self.uint256 {
+                true => (BigUint::one() << 256) - BigUint::one(),
+                false => (Felt252::zero() - Felt252::one()).to_biguint(),
+            };
+            let expected = match allowance == unlimited {
+                true => allowance,
+                false => allowance - &amount,
+            };
+            if after.allowances[&key] != expected {
+                return Err(format!(
+                    "transfer_from_allowance: allowance {} != {} expected",
+                    after.allowances[&key], expected
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Execute the calls encoded in `data` on `state` and check the ERC20 properties after each successful call
+    pub fn run(
+        &self,
+        runner: &RunnerStarknet,
+        state: &mut StarknetState,
+        data: &Vec<Felt252>,
+    ) -> Result<RunnerOutput, String> {
+        let calls = self.decode(data);
+        let sequence: Vec<SequenceCall> = calls
+            .iter()
+            .map(|(caller, call)| {
+                let (function, calldata) = self.calldata(call);
+                SequenceCall {
+                    caller: Some(caller.clone()),
+                    function: function,
+                    calldata: calldata,
+                }
+            })
+            .collect();
+        // Values read after the previous successful call
+        let mut before: Option<Erc20Snapshot> = None;
+        return runner.execute_sequence(state, &sequence, |index, _, state, call_state| {
+            let previous = match before.take() {
+                Some(snapshot) => snapshot,
+                None => self.snapshot(runner, state)?,
+            };
+            let after = self.snapshot(runner, call_state)?;
+            let (caller, call) = &calls[index];
+            self.check_call(caller, call, &previous, &after)
+                .map_err(|e| format!("ERC20 property {} violated", e))?;
+            before = Some(after);
+            return Ok(());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn erc20(uint256: bool) -> Erc20 {
+        Erc20 {
+            total_supply: "totalSupply".to_string(),
+            balance_of: "balanceOf".to_string(),
+            allowance: Some("allowance".to_string()),
+            transfer: "transfer".to_string(),
+            transfer_from: Some("transferFrom".to_string()),
+            approve: Some("approve".to_string()),
+            mint: Some("mint".to_string()),
+            uint256: uint256,
+            length: 2,
+        }
+    }
+
+    fn account(index: usize) -> Felt252 {
+        Felt252::from(ERC20_ACCOUNTS[index])
+    }
+
+    /// Values of the token with the balances of the accounts and of the zero address,
+    /// and the allowance given by the first account to the second one
+    fn snapshot(total_supply: u64, balances: [u64; 4], allowance: BigUint) -> Erc20Snapshot {
+        let mut accounts: Vec<Felt252> = ERC20_ACCOUNTS.iter().map(|a| Felt252::from(*a)).collect();
+        accounts.push(Felt252::zero());
+        let mut snapshot = Erc20Snapshot {
+            total_supply: BigUint::from(total_supply),
+            balances: HashMap::new(),
+            allowances: HashMap::new(),
+        };
+        for (owner, balance) in accounts.iter().zip(balances) {
+            snapshot
+                .balances
+                .insert(owner.clone(), BigUint::from(balance));
+            for spender in &accounts {
+                snapshot
+                    .allowances
+                    .insert((owner.clone(), spender.clone()), BigUint::zero());
+            }
+        }
+        snapshot
+            .allowances
+            .insert((account(0), account(1)), allowance);
+        return snapshot;
+    }
+
+    fn transfer(amount: u64) -> Erc20Call {
+        Erc20Call::Transfer {
+            recipient: account(1),
+            amount: Felt252::from(amount),
+        }
+    }
+
+    fn transfer_from(amount: u64) -> Erc20Call {
+        Erc20Call::TransferFrom {
+            sender: account(0),
+            recipient: account(2),
+            amount: Felt252::from(amount),
+        }
+    }
+
+    fn check(
+        call: &Erc20Call,
+        before: &Erc20Snapshot,
+        after: &Erc20Snapshot,
+    ) -> Result<(), String> {
+        erc20(false).check_call(&account(0), call, before, after)
+    }
+
+    fn violated(result: Result<(), String>, property: &str) -> bool {
+        result.map_or_else(|e| e.starts_with(property), |_| false)
+    }
+
+    #[test]
+    fn test_total_supply() {
+        let before = snapshot(100, [60, 40, 0, 0], BigUint::zero());
+        let after = snapshot(100, [50, 50, 0, 0], BigUint::zero());
+        assert!(check(&transfer(10), &before, &after).is_ok());
+        let after = snapshot(101, [50, 50, 0, 0], BigUint::zero());
+        assert!(violated(
+            check(&transfer(10), &before, &after),
+            "total_supply"
+        ));
+        // A mint increases the supply by the amount
+        let mint = Erc20Call::Mint {
+            recipient: account(2),
+            amount: Felt252::from(10),
+        };
+        let after = snapshot(110, [60, 40, 10, 0], BigUint::zero());
+        assert!(check(&mint, &before, &after).is_ok());
+        let after = snapshot(100, [60, 40, 10, 0], BigUint::zero());
+        assert!(violated(check(&mint, &before, &after), "total_supply"));
+    }
+
+    #[test]
+    fn test_transfer_balance() {
+        let before = snapshot(100, [60, 40, 0, 0], BigUint::zero());
+        let after = snapshot(100, [50, 50, 0, 0], BigUint::zero());
+        assert!(check(&transfer(10), &before, &after).is_ok());
+        // The recipient did not receive the amount
+        let after = snapshot(100, [50, 45, 0, 0], BigUint::zero());
+        assert!(violated(
+            check(&transfer(10), &before, &after),
+            "transfer_balance"
+        ));
+        // More than the balance of the sender
+        let after = snapshot(100, [0, 110, 0, 0], BigUint::zero());
+        assert!(violated(
+            check(&transfer(70), &before, &after),
+            "transfer_balance"
+        ));
+        // A transfer to self does not change the balance
+        let to_self = Erc20Call::Transfer {
+            recipient: account(0),
+            amount: Felt252::from(10),
+        };
+        assert!(check(&to_self, &before, &before).is_ok());
+    }
+
+    #[test]
+    fn test_balance_supply() {
+        let approve = Erc20Call::Approve {
+            spender: account(1),
+            amount: Felt252::from(5),
+        };
+        let before = snapshot(100, [60, 40, 0, 0], BigUint::zero());
+        assert!(check(&approve, &before, &before).is_ok());
+        let before = snapshot(90, [60, 40, 0, 0], BigUint::zero());
+        assert!(violated(
+            check(&approve, &before, &before),
+            "balance_supply"
+        ));
+    }
+
+    #[test]
+    fn test_transfer_from_allowance() {
+        let caller = account(1);
+        let before = snapshot(100, [60, 40, 0, 0], BigUint::from(20u32));
+        let after = snapshot(100, [50, 40, 10, 0], BigUint::from(10u32));
+        let token = erc20(false);
+        assert!(token
+            .check_call(&caller, &transfer_from(10), &before, &after)
+            .is_ok());
+        // The allowance is not spent
+        let after = snapshot(100, [50, 40, 10, 0], BigUint::from(20u32));
+        assert!(violated(
+            token.check_call(&caller, &transfer_from(10), &before, &after),
+            "transfer_from_allowance"
+        ));
+        // More than the allowance
+        let before = snapshot(100, [60, 40, 0, 0], BigUint::from(5u32));
+        let after = snapshot(100, [50, 40, 10, 0], BigUint::zero());
+        assert!(violated(
+            token.check_call(&caller, &transfer_from(10), &before, &after),
+            "transfer_from_allowance"
+        ));
+    }
+
+    #[test]
+    fn test_unlimited_allowance() {
+        let caller = account(1);
+        // The maximal felt or Uint256 allowance is never spent
+        for (token, unlimited) in [
+            (
+                erc20(false),
+                (Felt252::zero() - Felt252::one()).to_biguint(),
+            ),
+            (erc20(true), (BigUint::one() << 256) - BigUint::one()),
+        ] {
+            let before = snapshot(100, [60, 40, 0, 0], unlimited.clone());
+            let after = snapshot(100, [50, 40, 10, 0], unlimited.clone());
+            assert!(token
+                .check_call(&caller, &transfer_from(10), &before, &after)
+                .is_ok());
+            let after = snapshot(100, [50, 40, 10, 0], unlimited - 10u32);
+            assert!(violated(
+                token.check_call(&caller, &transfer_from(10), &before, &after),
+                "transfer_from_allowance"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_zero_address() {
+        let before = snapshot(100, [60, 40, 0, 0], BigUint::zero());
+        let after = snapshot(100, [50, 40, 0, 10], BigUint::zero());
+        let to_zero = Erc20Call::Transfer {
+            recipient: Felt252::zero(),
+            amount: Felt252::from(10),
+        };
+        assert!(violated(check(&to_zero, &before, &after), "zero_address"));
+        let from_zero = Erc20Call::TransferFrom {
+            sender: Felt252::zero(),
+            recipient: account(1),
+            amount: Felt252::zero(),
+        };
+        assert!(violated(
+            check(&from_zero, &before, &before),
+            "zero_address"
+        ));
+        let after = snapshot(100, [50, 50, 0, 0], BigUint::zero());
+        assert!(check(&transfer(10), &before, &after).is_ok());
+    }
+
+    #[test]
+    fn test_decode() {
+        let token = erc20(false);
+        let data: Vec<Felt252> = vec![0, 0, 1, 0, 42, 1, 4, 3, 0, 7]
+            .into_iter()
+            .map(Felt252::from)
+            .collect();
+        let calls = token.decode(&data);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0, account(0));
+        match &calls[0].1 {
+            Erc20Call::Transfer { recipient, amount } => {
+                assert_eq!(recipient, &account(1));
+                assert_eq!(amount, &Felt252::from(42));
+            }
+            call => panic!("Unexpected call {:?}", call),
+        }
+        // The caller is never the zero address while the targets can be
+        assert_eq!(calls[1].0, account(1));
+        match &calls[1].1 {
+            Erc20Call::TransferFrom {
+                sender,
+                recipient,
+                amount,
+            } => {
+                assert_eq!(sender, &account(0));
+                assert_eq!(recipient, &Felt252::zero());
+                assert_eq!(amount, &Felt252::from(7));
+            }
+            call => panic!("Unexpected call {:?}", call),
+        }
+        // Only the available calls are decoded
+        let mut token = erc20(false);
+        token.transfer_from = None;
+        token.approve = None;
+        token.mint = None;
+        let calls = token.decode(&data);
+        assert!(matches!(calls[1].1, Erc20Call::Transfer { .. }));
+    }
+
+    #[test]
+    fn test_amount() {
+        let amount = Felt252::from((BigUint::one() << 128) + 5u32);
+        let token = erc20(true);
+        let calldata = token.encode_amount(&amount);
+        assert_eq!(calldata, vec![Felt252::from(5), Felt252::from(1)]);
+        assert_eq!(token.decode_amount(&calldata), amount.to_biguint());
+        let token = erc20(false);
+        assert_eq!(token.encode_amount(&amount), vec![amount.clone()]);
+        assert_eq!(
+            token.decode_amount(&vec![amount.clone()]),
+            amount.to_biguint()
+        );
+        assert_eq!(token.decode_amount(&Vec::new()), BigUint::zero());
+    }
+}
@@ -0,0 +1,107 @@
+use felt::Felt252;
+use serde_json::Value;
+use starknet_rs::services::api::contract_classes::deprecated_contract_class::ContractClass;
+use starknet_rs::utils::{calculate_sn_keccak, Address, ClassHash};
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+use crate::json::json_parser::parse_felt;
+
+/// Contract deployed next to the fuzzed contract
+#[derive(Clone)]
+pub struct Deployment {
+    /// Path of the JSON artifact
+    pub artifact: String,
+    pub address: Address,
+    pub class_hash: ClassHash,
+    pub contract_class: ContractClass,
+    /// Calldata of the constructor, the constructor is not called if None
+    pub constructor_calldata: Option<Vec<Felt252>>,
+}
+
+/// Contracts deployed in the state before the executions
+/// Artifact paths are relative to the manifest file
+/// ```json
+/// {
+///     "contracts": [
+///         {"artifact": "token.json", "address": "0x2000", "constructor": ["0x1000", "1000000"]},
+///         {"artifact": "oracle.json", "address": "0x3000"}
+///     ]
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Manifest {
+    pub deployments: Vec<Deployment>,
+}
+
+/// Parse a felt of the manifest written as a string or a number
+fn get_felt(value: &Value) -> Option<Felt252> {
+    match value {
+        Value::String(value) => parse_felt(value).ok(),
+        Value::Number(value) => parse_felt(&value.to_string()).ok(),
+        _ => None,
+    }
+}
+
+/// Parse a contract of the manifest
+fn parse_deployment(folder: &Path, entry: &Value) -> Result<Deployment, String> {
+    let artifact = entry["artifact"]
+        .as_str()
+        .ok_or("missing artifact".to_string())?;
+    let address = get_felt(&entry["address"]).ok_or("missing or invalid address".to_string())?;
+    let constructor_calldata = match entry.get("constructor") {
+        Some(calldata) => {
+            let mut values = Vec::<Felt252>::new();
+            for value in calldata
+                .as_array()
+                .ok_or("constructor calldata is not an array".to_string())?
+            {
+                values.push(get_felt(value).ok_or(format!("invalid calldata {}", value))?);
+            }
+            Some(values)
+        }
+        None => None,
+    };
+    let path = folder.join(artifact);
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let contract_class = ContractClass::from_str(contents.as_str())
+        .map_err(|e| format!("could not get contract class of {}: {:?}", artifact, e))?;
+    return Ok(Deployment {
+        artifact: artifact.to_string(),
+        address: Address(address),
+        // Identical artifacts share the same class
+        class_hash: calculate_sn_keccak(contents.as_bytes()),
+        contract_class: contract_class,
+        constructor_calldata: constructor_calldata,
+    });
+}
+
+impl Manifest {
+    /// Load the manifest from a JSON file
+    pub fn load(filename: &String) -> Self {
+        let contents = fs::read_to_string(filename).expect("Failed to read the manifest file");
+        let data: Value = serde_json::from_str(&contents).expect("JSON was not well-formatted");
+        let folder = Path::new(filename).parent().unwrap_or(Path::new(""));
+        let mut deployments = Vec::<Deployment>::new();
+        for (index, entry) in data["contracts"]
+            .as_array()
+            .expect("Failed to get contracts from manifest file as array")
+            .iter()
+            .enumerate()
+        {
+            match parse_deployment(folder, entry) {
+                Ok(deployment) => deployments.push(deployment),
+                Err(e) => {
+                    eprintln!("Error: manifest contract {}: {}", index, e);
+                    process::exit(1);
+                }
+            }
+        }
+        return Manifest {
+            deployments: deployments,
+        };
+    }
+}
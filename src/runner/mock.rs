@@ -0,0 +1,165 @@
+use felt::Felt252;
+use num_traits::Zero;
+use starknet_rs::services::api::contract_classes::deprecated_contract_class::ContractClass;
+use starknet_rs::utils::{calculate_sn_keccak, Address, ClassHash};
+use std::fs;
+use std::process;
+use std::str::FromStr;
+
+use crate::cli::config::MockConfig;
+use crate::json::json_parser::parse_felt;
+
+/// Artifact of the mock contract compiled from `mocks/mock.cairo`
+pub const DEFAULT_MOCK_ARTIFACT: &str = "mocks/mock.json";
+
+/// Contract whose return values are taken from the fuzz input
+#[derive(Debug, Clone)]
+pub struct Mock {
+    pub address: Address,
+    /// Mocked functions as (name, number of returned values)
+    pub functions: Vec<(String, usize)>,
+}
+
+/// Mock contracts deployed in the state
+/// The values returned by each mocked function are taken from the end of the input
+#[derive(Clone)]
+pub struct Mocks {
+    pub class_hash: ClassHash,
+    pub contract_class: ContractClass,
+    pub mocks: Vec<Mock>,
+}
+
+impl Mocks {
+    /// Load the mock artifact and the mocks declared in the config
+    pub fn new(artifact: &String, mocks_config: &Vec<MockConfig>) -> Self {
+        let contents = match fs::read_to_string(artifact) {
+            Ok(contents) => contents,
+            Err(_) => {
+                eprintln!(
+                    "Error: Could not read the mock artifact {}, compile it with `starknet-compile mocks/mock.cairo --output {}`",
+                    artifact, artifact
+                );
+                process::exit(1)
+            }
+        };
+        let mut mocks = Vec::<Mock>::new();
+        for mock in mocks_config {
+            let address = match parse_felt(&mock.address) {
+                Ok(address) => address,
+                Err(e) => {
+                    eprintln!("Error: Invalid mock address: {}", e);
+                    process::exit(1)
+                }
+            };
+            mocks.push(Mock {
+                address: Address(address),
+                functions: mock
+                    .functions
+                    .iter()
+                    .map(|function| (function.name.clone(), function.outputs))
+                    .collect(),
+            });
+        }
+        return Mocks {
+            class_hash: calculate_sn_keccak(contents.as_bytes()),
+            contract_class: ContractClass::from_str(contents.as_str())
+                .expect("could not get contractclass"),
+            mocks: mocks,
+        };
+    }
+
+    /// Number of felts taken from the input for the responses
+    pub fn input_size(&self) -> usize {
+        self.mocks
+            .iter()
+            .flat_map(|mock| mock.functions.iter().map(|(_, outputs)| outputs))
+            .sum()
+    }
+
+    /// Names of the responses saved in the corpus
+    pub fn type_args(&self) -> Vec<String> {
+        let mut type_args = Vec::<String>::new();
+        for mock in &self.mocks {
+            for (name, outputs) in &mock.functions {
+                for index in 0..*outputs {
+                    type_args.push(format!("mock {} {}[{}]: felt", mock.address.0, name, index));
+                }
+            }
+        }
+        return type_args;
+    }
+
+    /// Storage of the mocks returning the responses in `data`, as (address, key, value)
+    /// The number of values of a selector is stored at the key `selector` and the values
+    /// at the keys `selector + 1 + index`
+    pub fn storage(&self, data: &[Felt252]) -> Vec<(Address, Felt252, Felt252)> {
+        let mut storage = Vec::new();
+        let mut values = data.iter();
+        for mock in &self.mocks {
+            for (name, outputs) in &mock.functions {
+                let selector = Felt252::from_bytes_be(&calculate_sn_keccak(name.as_bytes()));
+                storage.push((
+                    mock.address.clone(),
+                    selector.clone(),
+                    Felt252::from(*outputs),
+                ));
+                for index in 0..*outputs {
+                    let value = values.next().cloned().unwrap_or(Felt252::zero());
+                    storage.push((
+                        mock.address.clone(),
+                        selector.clone() + Felt252::from(1 + index),
+                        value,
+                    ));
+                }
+            }
+        }
+        return storage;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::config::MockFunction;
+
+    fn mocks() -> Mocks {
+        let config = MockConfig {
+            address: "0x3000".to_string(),
+            functions: vec![
+                MockFunction {
+                    name: "get_price".to_string(),
+                    outputs: 2,
+                },
+                MockFunction {
+                    name: "decimals".to_string(),
+                    outputs: 1,
+                },
+            ],
+        };
+        return Mocks::new(&DEFAULT_MOCK_ARTIFACT.to_string(), &vec![config]);
+    }
+
+    #[test]
+    fn test_mock_storage() {
+        let mocks = mocks();
+        assert_eq!(mocks.input_size(), 3);
+        let selector = Felt252::from_bytes_be(&calculate_sn_keccak(b"get_price"));
+        let storage = mocks.storage(&[Felt252::from(7), Felt252::from(8)]);
+        let address = Address(0x3000.into());
+        assert_eq!(storage.len(), 5);
+        assert_eq!(
+            storage[0],
+            (address.clone(), selector.clone(), Felt252::from(2))
+        );
+        assert_eq!(
+            storage[2],
+            (
+                address.clone(),
+                selector + Felt252::from(2),
+                Felt252::from(8)
+            )
+        );
+        // Missing responses are zero
+        assert_eq!(storage[4].2, Felt252::zero());
+    }
+}
@@ -1,3 +1,10 @@
+pub mod access_control;
 pub mod cairo_runner;
+pub mod erc20;
+pub mod manifest;
+pub mod mock;
+pub mod model;
 pub mod runner;
+pub mod snapshot;
 pub mod starknet_runner;
+pub mod storage_decoder;
@@ -0,0 +1,68 @@
+use felt::Felt252;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Number of calls of a sequence when none is configured
+pub const DEFAULT_SEQUENCE_LENGTH: usize = 10;
+
+/// Call executed on the contract
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub function: String,
+    pub calldata: Vec<Felt252>,
+    pub retdata: Vec<Felt252>,
+}
+
+/// Shadow model of a Starknet contract registered through the library API
+///
+/// The model is updated in lockstep with every successful call of a sequence
+/// and compared with the storage of the contract after each of them.
+/// ```ignore
+/// #[derive(Default)]
+/// struct CounterModel {
+///     counter: Felt252,
+/// }
+/// impl Model for CounterModel {
+///     fn functions(&self) -> Vec<String> {
+///         vec!["increase".to_string()]
+///     }
+///     fn apply(&mut self, call: &Call) {
+///         self.counter += &call.calldata[0];
+///     }
+///     fn check(&self, storage: &HashMap<Felt252, Felt252>) -> Result<(), String> {
+///         expect_storage(storage, &COUNTER_ADDRESS, &self.counter)
+///     }
+/// }
+/// fuzzer.register_model(Arc::new(|| Box::new(CounterModel::default())));
+/// ```
+pub trait Model {
+    /// External functions of the contract called in the sequences
+    fn functions(&self) -> Vec<String>;
+
+    /// Update the model with a call that succeeded on the contract
+    fn apply(&mut self, call: &Call);
+
+    /// Compare the model with the storage of the contract (key => value)
+    /// Returns the divergence message if the storage is not the expected one
+    fn check(&self, storage: &HashMap<Felt252, Felt252>) -> Result<(), String>;
+}
+
+/// Create a new model for every sequence
+pub type ModelFactory = Arc<dyn Fn() -> Box<dyn Model> + Send + Sync>;
+
+/// Check the value of the storage slot `key`, slots never written are 0
+pub fn expect_storage(
+    storage: &HashMap<Felt252, Felt252>,
+    key: &Felt252,
+    expected: &Felt252,
+) -> Result<(), String> {
+    let value = storage.get(key).cloned().unwrap_or(Felt252::zero());
+    if &value != expected {
+        return Err(format!(
+            "storage {} = {} != {} expected",
+            key, value, expected
+        ));
+    }
+    return Ok(());
+}
@@ -1,9 +1,150 @@
+use cairo_rs::vm::runners::cairo_runner::ExecutionResources;
 use felt::Felt252;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Event emitted by a contract
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub from_address: Felt252,
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
+/// Message sent to L1 with `send_message_to_l1`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct L2ToL1Message {
+    pub from_address: Felt252,
+    pub to_address: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
+/// Storage slot written by an execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageWrite {
+    /// Address of the contract
+    pub address: Felt252,
+    pub key: Felt252,
+    /// `@storage_var` name and key arguments of the slot, the raw key if unknown
+    pub name: String,
+    pub value: Felt252,
+}
+
+/// Result of the execution of a function
+#[derive(Debug, Clone, Default)]
+pub struct RunnerOutput {
+    /// Trace of the execution as (pc, fp), including the internal calls for Starknet
+    pub trace: Vec<(u32, u32)>,
+    /// Values returned by the function
+    pub retdata: Vec<Felt252>,
+    /// Resources used by the execution
+    pub resources: ExecutionResources,
+    /// Storage of the contract written by the execution (key => value)
+    pub storage: HashMap<Felt252, Felt252>,
+    /// Events emitted by the execution, in emission order
+    pub events: Vec<Event>,
+    /// Messages sent to L1 by the execution, in emission order
+    pub messages: Vec<L2ToL1Message>,
+}
 
 pub trait Runner {
     fn runner(
         self,
         func_name: &String,
         data: &Vec<Felt252>,
-    ) -> Result<Option<Vec<(u32, u32)>>, String>;
+    ) -> Result<Option<RunnerOutput>, String>;
+}
+
+/// Format felts as a readable list of decimal values
+pub fn format_felts(values: &Vec<Felt252>) -> String {
+    format!(
+        "[{}]",
+        values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Format events as `from_address keys [...] data [...]`
+pub fn format_events(events: &Vec<Event>) -> String {
+    format!(
+        "[{}]",
+        events
+            .iter()
+            .map(|event| format!(
+                "{} keys {} data {}",
+                event.from_address,
+                format_felts(&event.keys),
+                format_felts(&event.data)
+            ))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Format L2 to L1 messages as `from_address -> to_address [payload]`
+pub fn format_messages(messages: &Vec<L2ToL1Message>) -> String {
+    format!(
+        "[{}]",
+        messages
+            .iter()
+            .map(|message| format!(
+                "{} -> {} {}",
+                message.from_address,
+                message.to_address,
+                format_felts(&message.payload)
+            ))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Format storage writes as `address: name = value`
+pub fn format_storage_writes(writes: &Vec<StorageWrite>) -> String {
+    format!(
+        "[{}]",
+        writes
+            .iter()
+            .map(|write| format!("{}: {} = {}", write.address, write.name, write.value))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felts(values: &[u64]) -> Vec<Felt252> {
+        values.iter().map(|value| Felt252::from(*value)).collect()
+    }
+
+    #[test]
+    fn test_format_events() {
+        let events = vec![
+            Event {
+                from_address: Felt252::from(1111),
+                keys: felts(&[1]),
+                data: felts(&[2, 3]),
+            },
+            Event {
+                from_address: Felt252::from(2222),
+                keys: Vec::new(),
+                data: felts(&[4]),
+            },
+        ];
+        assert_eq!(
+            format_events(&events),
+            "[1111 keys [1] data [2, 3], 2222 keys [] data [4]]"
+        );
+        let messages = vec![L2ToL1Message {
+            from_address: Felt252::from(1111),
+            to_address: Felt252::from(42),
+            payload: felts(&[5, 6]),
+        }];
+        assert_eq!(format_messages(&messages), "[1111 -> 42 [5, 6]]");
+        assert_eq!(format_events(&Vec::new()), "[]");
+    }
 }
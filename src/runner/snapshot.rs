@@ -0,0 +1,119 @@
+use felt::Felt252;
+use serde_json::Value;
+use std::fs;
+use std::process;
+
+use super::storage_decoder::{parse_storage_key, StorageDecoder};
+use crate::json::json_parser::parse_felt;
+
+/// Storage slot preloaded before the executions
+#[derive(Debug, Clone)]
+pub struct SnapshotEntry {
+    /// Address of the contract, the fuzzed contract wherever it runs if None
+    pub address: Option<Felt252>,
+    pub key: Felt252,
+    pub value: Felt252,
+}
+
+/// Initial storage of the contracts loaded from a JSON file
+/// ```json
+/// {
+///     "storage": [
+///         {"key": "_owner", "value": "0x1000"},
+///         {"key": "balances(0x1001)", "value": "1000"},
+///         {"address": "1111", "key": "0x2a", "value": "1"}
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// Get a field of a state file entry written as a string or a number
+fn get_field(entry: &Value, name: &str) -> Option<String> {
+    match &entry[name] {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Parse an entry of the state file
+/// The names of the storage variables of the fuzzed contract are checked with `decoder`
+fn parse_entry(entry: &Value, decoder: &StorageDecoder) -> Result<SnapshotEntry, String> {
+    let address = match get_field(entry, "address") {
+        Some(address) => Some(parse_felt(&address).map_err(|e| format!("address: {}", e))?),
+        None => None,
+    };
+    let key = get_field(entry, "key").ok_or("missing key".to_string())?;
+    let decoder = match address {
+        Some(_) => StorageDecoder::default(),
+        None => decoder.clone(),
+    };
+    let value = get_field(entry, "value").ok_or("missing value".to_string())?;
+    return Ok(SnapshotEntry {
+        address: address,
+        key: parse_storage_key(&key, &decoder)?,
+        value: parse_felt(&value).map_err(|e| format!("value: {}", e))?,
+    });
+}
+
+impl Snapshot {
+    /// Load the snapshot from a JSON file
+    pub fn load(filename: &String, decoder: &StorageDecoder) -> Self {
+        let contents = fs::read_to_string(filename).expect("Failed to read the state file");
+        let data: Value = serde_json::from_str(&contents).expect("JSON was not well-formatted");
+        let mut entries = Vec::<SnapshotEntry>::new();
+        for (index, entry) in data["storage"]
+            .as_array()
+            .expect("Failed to get storage from state file as array")
+            .iter()
+            .enumerate()
+        {
+            match parse_entry(entry, decoder) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    eprintln!("Error: state file entry {}: {}", index, e);
+                    process::exit(1);
+                }
+            }
+        }
+        return Snapshot { entries: entries };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use starknet_rs::utils::get_storage_var_address;
+
+    #[test]
+    fn test_load_snapshot() {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let decoder = StorageDecoder::from_artifact(&contents);
+        let snapshot = Snapshot::load(&"tests/storage_state.json".to_string(), &decoder);
+        assert_eq!(snapshot.entries.len(), 1);
+        let entry = &snapshot.entries[0];
+        assert_eq!(entry.address, None);
+        assert_eq!(
+            entry.key,
+            get_storage_var_address("_counter", &[]).expect("Could not get address")
+        );
+        assert_eq!(entry.value, Felt252::new(100));
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let decoder = StorageDecoder::default();
+        let entry = json!({"address": 1111, "key": "0x2a", "value": "-1"});
+        let entry = parse_entry(&entry, &decoder).expect("Could not parse entry");
+        assert_eq!(entry.address, Some(Felt252::new(1111)));
+        assert_eq!(entry.key, Felt252::new(42));
+        assert_eq!(entry.value, -Felt252::new(1));
+        // Values are not reduced modulo P
+        let entry = json!({"key": "0x2a", "value": Felt252::prime().to_string()});
+        assert!(parse_entry(&entry, &decoder).is_err());
+    }
+}
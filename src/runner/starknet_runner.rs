@@ -1,10 +1,13 @@
 use cairo_rs::felt::Felt252;
-use num_traits::Zero;
+use num_traits::{ToPrimitive, Zero};
 use starknet_rs::services::api::contract_classes::deprecated_contract_class::EntryPointType;
+use starknet_rs::state::state_api::{State, StateReader};
+use starknet_rs::state::state_cache::StorageEntry;
+use starknet_rs::utils::{calculate_sn_keccak, ClassHash};
 use starknet_rs::{
     definitions::{block_context::BlockContext, constants::TRANSACTION_VERSION},
     execution::{
-        execution_entry_point::ExecutionEntryPoint, CallType, TransactionExecutionContext,
+        execution_entry_point::ExecutionEntryPoint, CallInfo, CallType, TransactionExecutionContext,
     },
     services::api::contract_classes::deprecated_contract_class::ContractClass,
     state::cached_state::CachedState,
@@ -12,41 +15,333 @@ use starknet_rs::{
     utils::Address,
 };
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use super::runner::Runner;
+use super::access_control::{AccessControl, ACCESS_CONTROL_ACCOUNTS, DEPLOYER_ADDRESS};
+use super::erc20::{Erc20, ERC20_ACCOUNTS};
+use super::manifest::Manifest;
+use super::mock::Mocks;
+use super::model::{Call, ModelFactory};
+use super::runner::{format_felts, Event, L2ToL1Message, Runner, RunnerOutput, StorageWrite};
+use super::snapshot::Snapshot;
+use super::storage_decoder::StorageDecoder;
+use crate::fuzzer::revert::diff_failure;
+use crate::json::json_parser::Function;
+
+/// Address where the contract is deployed when the context is not fuzzed
+pub const CONTRACT_ADDRESS: u64 = 1111;
+
+/// State used to execute the contract
+pub type StarknetState = CachedState<InMemoryStateReader>;
+
+/// Fields of the execution context taken from the fuzz input
+pub const CONTEXT_FIELDS: [&str; 9] = [
+    "caller_address",
+    "contract_address",
+    "block_number",
+    "block_timestamp",
+    "sequencer_address",
+    "nonce",
+    "version",
+    "signature_r",
+    "signature_s",
+];
+
+/// Arguments of the execution context saved in the corpus
+pub fn context_type_args() -> Vec<String> {
+    CONTEXT_FIELDS
+        .iter()
+        .map(|field| format!("{}: felt", field))
+        .collect()
+}
+
+/// Execution context of the calls
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+    pub caller_address: Address,
+    pub contract_address: Address,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub sequencer_address: Address,
+    pub nonce: Felt252,
+    pub version: Felt252,
+    pub signature: Vec<Felt252>,
+}
+
+impl ExecutionContext {
+    /// Decode the context from the first `CONTEXT_FIELDS.len()` felts of the input
+    pub fn decode(data: &[Felt252]) -> Self {
+        // Block values are truncated to their 64 low bits
+        let to_u64 = |value: &Felt252| value.to_biguint().iter_u64_digits().next().unwrap_or(0);
+        return ExecutionContext {
+            caller_address: Address(data[0].clone()),
+            contract_address: Address(data[1].clone()),
+            block_number: to_u64(&data[2]),
+            block_timestamp: to_u64(&data[3]),
+            sequencer_address: Address(data[4].clone()),
+            nonce: data[5].clone(),
+            version: data[6].clone(),
+            signature: vec![data[7].clone(), data[8].clone()],
+        };
+    }
+}
+
+/// Call of a sequence, made by the caller of the execution context if `caller` is `None`
+#[derive(Debug, Clone)]
+pub struct SequenceCall {
+    pub caller: Option<Felt252>,
+    pub function: String,
+    pub calldata: Vec<Felt252>,
+}
+
+/// Sequences of calls executed in lockstep with a model
+/// Each call is encoded in the input as `[function index, args...]`
+#[derive(Clone)]
+pub struct Sequence {
+    /// Functions called in the sequence
+    pub functions: Vec<Function>,
+    /// Number of calls of a sequence
+    pub length: usize,
+    /// Create the model of the contract
+    pub model: ModelFactory,
+}
+
+impl Sequence {
+    /// Number of felts used to encode one call
+    pub fn call_size(&self) -> usize {
+        1 + self
+            .functions
+            .iter()
+            .map(|function| function.num_args as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of felts used to encode the whole sequence
+    pub fn input_size(&self) -> usize {
+        self.length * self.call_size()
+    }
+
+    /// Decode the calls of the sequence from the input
+    pub fn decode(&self, data: &Vec<Felt252>) -> Vec<SequenceCall> {
+        let mut calls = Vec::new();
+        for chunk in data.chunks(self.call_size()) {
+            let index = (chunk[0].to_biguint() % self.functions.len())
+                .to_usize()
+                .expect("Could not get function index");
+            let function = &self.functions[index];
+            let args = chunk[1..].iter().take(function.num_args as usize).cloned();
+            calls.push(SequenceCall {
+                caller: None,
+                function: function.name.clone(),
+                calldata: args.collect(),
+            });
+        }
+        return calls;
+    }
+}
 
 #[derive(Clone)]
 pub struct RunnerStarknet {
     contract_class: ContractClass,
+    /// Address where the contract is deployed
+    address: Address,
+    /// Class hash of the contract
+    class_hash: ClassHash,
+    /// View functions checked against the post-state of every call
+    invariants: Vec<Function>,
+    /// Other version of the contract executed on the same inputs
+    diff_runner: Option<Box<RunnerStarknet>>,
+    /// Sequences executed instead of single calls
+    sequence: Option<Sequence>,
+    /// ERC20 properties checked on sequences of token calls
+    erc20: Option<Erc20>,
+    /// Access control checked on sequences of calls from several callers
+    access_control: Option<AccessControl>,
+    /// Take the execution context from the beginning of the input
+    fuzz_context: bool,
+    /// Execution context of the calls, the default one if None
+    context: Option<ExecutionContext>,
+    /// Storage preloaded in the state
+    snapshot: Snapshot,
+    /// Other contracts deployed in the state
+    manifest: Manifest,
+    /// Mock contracts returning values taken from the end of the input
+    mocks: Option<Mocks>,
+    /// Responses of the mocks for the current execution
+    mock_responses: Vec<Felt252>,
+    /// Type of the fuzzed entrypoint
+    entry_point_type: EntryPointType,
+    /// Names of the storage variables of the contract
+    storage_decoder: StorageDecoder,
+    /// State where the contracts are deployed, shared by the executions
+    base_state: Option<Arc<StarknetState>>,
 }
 
 impl RunnerStarknet {
     pub fn new(contract_class: &ContractClass) -> Self {
         return RunnerStarknet {
             contract_class: contract_class.clone(),
+            address: Address(CONTRACT_ADDRESS.into()),
+            class_hash: [1; 32],
+            invariants: Vec::new(),
+            diff_runner: None,
+            sequence: None,
+            erc20: None,
+            access_control: None,
+            fuzz_context: false,
+            context: None,
+            snapshot: Snapshot::default(),
+            manifest: Manifest::default(),
+            mocks: None,
+            mock_responses: Vec::new(),
+            entry_point_type: EntryPointType::External,
+            storage_decoder: StorageDecoder::default(),
+            base_state: None,
         };
     }
-}
 
-impl Runner for RunnerStarknet {
-    fn runner(
-        self,
-        func_entrypoint: &String,
-        data: &Vec<Felt252>,
-    ) -> Result<Option<Vec<(u32, u32)>>, String> {
-        // ---------------------------------------------------------
-        //  Create program and entry point types for contract class
-        // ---------------------------------------------------------
-
-        let entry_points_by_type = self.contract_class.entry_points_by_type();
-        let entrypoint_selector = entry_points_by_type
-            .get(&EntryPointType::External) // Should we call only "External" functions?
-            .unwrap()
-            .iter()
-            .find(|entrypoint| &entrypoint.offset().to_string() == func_entrypoint)
-            .unwrap()
-            .selector()
-            .clone();
+    /// Set the invariants to check after each call
+    pub fn invariants(mut self, invariants: &Vec<Function>) -> Self {
+        self.invariants = invariants.clone();
+        self
+    }
+
+    /// Set the other version of the contract used for differential fuzzing
+    /// The sequences of this runner are executed on the other version too
+    pub fn differential(mut self, diff_runner: &RunnerStarknet) -> Self {
+        let mut diff_runner = diff_runner.clone();
+        diff_runner.sequence = self.sequence.clone();
+        diff_runner.erc20 = self.erc20.clone();
+        diff_runner.access_control = self.access_control.clone();
+        self.diff_runner = Some(Box::new(diff_runner));
+        self
+    }
+
+    /// Execute sequences of calls checked against a model
+    pub fn sequence(mut self, sequence: &Sequence) -> Self {
+        self.sequence = Some(sequence.clone());
+        self.diff_runner = self
+            .diff_runner
+            .map(|diff_runner| Box::new(diff_runner.sequence(sequence)));
+        self
+    }
+
+    /// Execute sequences of token calls checked against the ERC20 properties
+    pub fn erc20(mut self, erc20: &Erc20) -> Self {
+        self.erc20 = Some(erc20.clone());
+        self
+    }
+
+    /// Take the execution context of the calls from the beginning of the input
+    pub fn fuzz_context(mut self, fuzz_context: bool) -> Self {
+        self.fuzz_context = fuzz_context;
+        self
+    }
+
+    /// Set the storage preloaded in the state before the executions
+    pub fn snapshot(mut self, snapshot: &Snapshot) -> Self {
+        self.snapshot = snapshot.clone();
+        self.base_state = None;
+        self
+    }
+
+    /// Set the other contracts deployed in the state
+    pub fn manifest(mut self, manifest: &Manifest) -> Self {
+        self.manifest = manifest.clone();
+        self.base_state = None;
+        self
+    }
+
+    /// Deploy mock contracts returning values taken from the end of the input
+    pub fn mocks(mut self, mocks: &Option<Mocks>) -> Self {
+        self.mocks = mocks.clone();
+        self.base_state = None;
+        self
+    }
+
+    /// Fuzz the `@l1_handler` or the `@constructor` of the contract given its decorators
+    pub fn entry_point(mut self, decorators: &Vec<String>) -> Self {
+        self.entry_point_type = if decorators.contains(&"l1_handler".to_string()) {
+            EntryPointType::L1Handler
+        } else if decorators.contains(&"constructor".to_string()) {
+            EntryPointType::Constructor
+        } else {
+            EntryPointType::External
+        };
+        self
+    }
+
+    /// Set the decoder used to name the storage writes
+    pub fn storage_decoder(mut self, storage_decoder: &StorageDecoder) -> Self {
+        self.storage_decoder = storage_decoder.clone();
+        self
+    }
+
+    /// Execute sequences of calls from several callers to detect missing access control
+    pub fn access_control(mut self, access_control: &AccessControl) -> Self {
+        self.access_control = Some(access_control.clone());
+        self
+    }
+
+    /// Name of the mode executing the inputs as sequences of calls, if any
+    /// A runner executes a single mode since each one decodes the input its own way
+    pub fn sequence_mode(&self) -> Option<&'static str> {
+        if self.sequence.is_some() {
+            Some("registered model")
+        } else if self.erc20.is_some() {
+            Some("ERC20 property suite")
+        } else if self.access_control.is_some() {
+            Some("access-control detector")
+        } else {
+            None
+        }
+    }
+
+    /// Deploy the contracts and call the constructors of the manifest once,
+    /// every execution starts from a copy of this state
+    pub fn deploy(mut self) -> Result<Self, String> {
+        self.check_addresses()?;
+        self.base_state = Some(Arc::new(self.deployed_state()?));
+        if let Some(diff_runner) = self.diff_runner.take() {
+            self.diff_runner = Some(Box::new(diff_runner.deploy()?));
+        }
+        Ok(self)
+    }
+
+    /// Check that the contract, the mocks and the contracts of the manifest have different addresses
+    fn check_addresses(&self) -> Result<(), String> {
+        let mut addresses = vec![(
+            Address(CONTRACT_ADDRESS.into()),
+            "the fuzzed contract".to_string(),
+        )];
+        if let Some(mocks) = &self.mocks {
+            for mock in &mocks.mocks {
+                addresses.push((mock.address.clone(), "a mock".to_string()));
+            }
+        }
+        for deployment in &self.manifest.deployments {
+            addresses.push((deployment.address.clone(), deployment.artifact.clone()));
+        }
+        for (index, (address, name)) in addresses.iter().enumerate() {
+            if let Some((_, other)) = addresses[..index]
+                .iter()
+                .find(|(other, _)| other == address)
+            {
+                return Err(format!(
+                    "{} and {} are both deployed at {}",
+                    other, name, address.0
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the state where the contracts are deployed and the constructors of the manifest called
+    /// The fuzzed contract is deployed at its default address
+    fn deployed_state(&self) -> Result<StarknetState, String> {
+        let contract_address = Address(CONTRACT_ADDRESS.into());
+
         //* --------------------------------------------
         //*    Create state reader with class hash data
         //* --------------------------------------------
@@ -55,24 +350,192 @@ impl Runner for RunnerStarknet {
 
         //  ------------ contract data --------------------
 
-        let address = Address(1111.into());
-        let class_hash = [1; 32];
-
-        contract_class_cache.insert(class_hash, self.contract_class);
+        contract_class_cache.insert(self.class_hash, self.contract_class.clone());
         let mut state_reader = InMemoryStateReader::default();
         state_reader
             .address_to_class_hash_mut()
-            .insert(address.clone(), class_hash);
+            .insert(contract_address.clone(), self.class_hash);
+
+        //  ------------ other contracts --------------------
+
+        for deployment in &self.manifest.deployments {
+            contract_class_cache.insert(deployment.class_hash, deployment.contract_class.clone());
+            state_reader
+                .address_to_class_hash_mut()
+                .insert(deployment.address.clone(), deployment.class_hash);
+        }
+
+        //  ------------ mock contracts --------------------
+
+        if let Some(mocks) = &self.mocks {
+            contract_class_cache.insert(mocks.class_hash, mocks.contract_class.clone());
+            for mock in &mocks.mocks {
+                state_reader
+                    .address_to_class_hash_mut()
+                    .insert(mock.address.clone(), mocks.class_hash);
+            }
+        }
+
+        //  ------------ initial storage --------------------
+
+        for entry in &self.snapshot.entries {
+            let address = match &entry.address {
+                Some(address) => Address(address.clone()),
+                None => contract_address.clone(),
+            };
+            state_reader
+                .address_to_storage_mut()
+                .insert((address, entry.key.to_be_bytes()), entry.value.clone());
+        }
 
         //* ---------------------------------------
         //*    Create state with previous data
         //* ---------------------------------------
 
-        let mut state = CachedState::new(
-            std::sync::Arc::new(state_reader),
-            Some(contract_class_cache),
-            None,
+        let mut state = CachedState::new(Arc::new(state_reader), Some(contract_class_cache), None);
+
+        //* ---------------------------------------
+        //*    Call the constructors
+        //* ---------------------------------------
+
+        // The constructors do not depend on the fuzzed context
+        let mut deployer = self.clone();
+        deployer.context = None;
+        for deployment in &self.manifest.deployments {
+            if let Some(calldata) = &deployment.constructor_calldata {
+                deployer
+                    .execute_entry_point(
+                        &mut state,
+                        &deployment.address,
+                        &deployment.class_hash,
+                        &Address(0.into()),
+                        &"constructor".to_string(),
+                        EntryPointType::Constructor,
+                        calldata,
+                    )
+                    .map_err(|e| format!("constructor of {} failed: {}", deployment.artifact, e))?;
+            }
+        }
+        return Ok(state);
+    }
+
+    /// Create a new state where the contract is deployed
+    /// The mock responses of the current execution are written on a copy of the deployed state
+    pub fn init_state(&self) -> Result<StarknetState, String> {
+        let mut state = match &self.base_state {
+            Some(base_state) => base_state.as_ref().clone(),
+            None => self.deployed_state()?,
+        };
+
+        //  ------------ fuzzed address --------------------
+
+        // The contract is deployed again at its fuzzed address with a copy of its storage
+        if self.address != Address(CONTRACT_ADDRESS.into()) {
+            let storage = self.deployed_storage(&mut state);
+            state
+                .deploy_contract(self.address.clone(), self.class_hash)
+                .map_err(|e| {
+                    format!("Could not deploy the contract at {}: {}", self.address.0, e)
+                })?;
+            for (key, value) in storage {
+                state.set_storage_at(&(self.address.clone(), key), value);
+            }
+        }
+
+        //  ------------ mock responses --------------------
+
+        if let Some(mocks) = &self.mocks {
+            for (address, key, value) in mocks.storage(&self.mock_responses) {
+                state.set_storage_at(&(address, key.to_be_bytes()), value);
+            }
+        }
+        return Ok(state);
+    }
+
+    /// Storage of the contract at its default address on the deployed `state`,
+    /// preloaded from the snapshot or written by the constructors of the manifest
+    fn deployed_storage(&self, state: &mut StarknetState) -> HashMap<[u8; 32], Felt252> {
+        let address = Address(CONTRACT_ADDRESS.into());
+        let mut storage: HashMap<[u8; 32], Felt252> = self
+            .snapshot
+            .entries
+            .iter()
+            .filter(|entry| match &entry.address {
+                Some(entry_address) => Address(entry_address.clone()) == address,
+                None => true,
+            })
+            .map(|entry| (entry.key.to_be_bytes(), entry.value.clone()))
+            .collect();
+        storage.extend(
+            get_storage_writes(state)
+                .into_iter()
+                .filter(|((entry_address, _), _)| entry_address == &address)
+                .map(|((_, key), value)| (key, value)),
+        );
+        return storage;
+    }
+
+    /// Address where the contract runs for the fuzzed `address`, the default one if
+    /// it is zero or already used by a mock or a contract of the manifest
+    fn fuzzed_address(&self, address: &Address) -> Address {
+        let taken = address == &Address(0.into())
+            || self
+                .manifest
+                .deployments
+                .iter()
+                .any(|deployment| &deployment.address == address)
+            || self.mocks.as_ref().map_or(false, |mocks| {
+                mocks.mocks.iter().any(|mock| &mock.address == address)
+            });
+        match taken {
+            true => Address(CONTRACT_ADDRESS.into()),
+            false => address.clone(),
+        }
+    }
+
+    /// Execute the external function `func_name` with `data` as calldata on `state`
+    pub fn execute(
+        &self,
+        state: &mut StarknetState,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<CallInfo, String> {
+        return self.execute_from(state, &self.caller_address(), func_name, data);
+    }
+
+    /// Execute the external function `func_name` called by `caller_address`
+    pub fn execute_from(
+        &self,
+        state: &mut StarknetState,
+        caller_address: &Address,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<CallInfo, String> {
+        return self.execute_entry_point(
+            state,
+            &self.address,
+            &self.class_hash,
+            caller_address,
+            func_name,
+            EntryPointType::External,
+            data,
         );
+    }
+
+    /// Execute the entrypoint `func_name` of the contract deployed at `address`
+    fn execute_entry_point(
+        &self,
+        state: &mut StarknetState,
+        address: &Address,
+        class_hash: &ClassHash,
+        caller_address: &Address,
+        func_name: &String,
+        entry_point_type: EntryPointType,
+        data: &Vec<Felt252>,
+    ) -> Result<CallInfo, String> {
+        // The selector of an entrypoint is the starknet keccak of its name
+        let entrypoint_selector =
+            Felt252::from_bytes_be(&calculate_sn_keccak(func_name.as_bytes()));
 
         //* ------------------------------------
         //*    Create execution entry point
@@ -82,35 +545,50 @@ impl Runner for RunnerStarknet {
         for i in data {
             calldata.push((*i).clone());
         }
-        let caller_address = Address(0000.into());
-        let entry_point_type = EntryPointType::External;
         let exec_entry_point = ExecutionEntryPoint::new(
             address.clone(),
             calldata.clone(),
             entrypoint_selector,
-            caller_address,
+            caller_address.clone(),
             entry_point_type,
             Some(CallType::Delegate),
-            Some(class_hash),
+            Some(*class_hash),
             0,
         );
 
         //* --------------------
         //*   Execute contract
         //* ---------------------
-        let block_context = BlockContext::default();
-        let mut tx_execution_context = TransactionExecutionContext::new(
-            Address(0.into()),
-            Felt252::zero(),
-            Vec::new(),
-            0,
-            10.into(),
-            block_context.invoke_tx_max_n_steps(),
-            TRANSACTION_VERSION.clone(),
-        );
+        let mut block_context = BlockContext::default();
+        let mut tx_execution_context = match &self.context {
+            Some(context) => {
+                let block_info = block_context.block_info_mut();
+                block_info.block_number = context.block_number;
+                block_info.block_timestamp = context.block_timestamp;
+                block_info.sequencer_address = context.sequencer_address.clone();
+                TransactionExecutionContext::new(
+                    caller_address.clone(),
+                    Felt252::zero(),
+                    context.signature.clone(),
+                    0,
+                    context.nonce.clone(),
+                    block_context.invoke_tx_max_n_steps(),
+                    context.version.clone(),
+                )
+            }
+            None => TransactionExecutionContext::new(
+                Address(0.into()),
+                Felt252::zero(),
+                Vec::new(),
+                0,
+                10.into(),
+                block_context.invoke_tx_max_n_steps(),
+                TRANSACTION_VERSION.clone(),
+            ),
+        };
         let mut resources_manager = ExecutionResourcesManager::default();
         match exec_entry_point.execute(
-            &mut state,
+            state,
             &block_context,
             &mut resources_manager,
             &mut tx_execution_context,
@@ -119,9 +597,842 @@ impl Runner for RunnerStarknet {
             true,
         ) {
             Ok(exec_info) => {
-                return Ok(Some(exec_info.call_info.unwrap().trace));
+                return Ok(exec_info.call_info.expect("Could not get call info"));
             }
             Err(e) => return Err(e.to_string()),
         };
     }
+
+    /// Call every invariant on `state`
+    /// An invariant is violated if it fails or if it returns 0
+    /// `history` is the list of calls that led to this state
+    pub fn check_invariants(
+        &self,
+        state: &mut StarknetState,
+        history: &Vec<SequenceCall>,
+    ) -> Result<(), String> {
+        for invariant in &self.invariants {
+            let args = vec![Felt252::zero(); invariant.num_args as usize];
+            let violation = match self.execute(state, &invariant.name, &args) {
+                Ok(call_info) => match call_info.retdata.first() {
+                    Some(value) if value.is_zero() => Some("returned 0".to_string()),
+                    _ => None,
+                },
+                Err(e) => Some(e),
+            };
+            if let Some(reason) = violation {
+                return Err(format!(
+                    "Invariant {} violated ({}) after calls: {}",
+                    invariant.name,
+                    reason,
+                    format_history(history)
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Execute `func_name` on a new state and check the invariants on the post-state
+    /// A constructor is executed as the deployment of the contract, so the
+    /// invariants are checked on the initial state
+    pub fn run(
+        &self,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<(CallInfo, StarknetState), String> {
+        let mut state = self.init_state()?;
+        let call_info = self.execute_call(&mut state, func_name, data)?;
+        return Ok((call_info, state));
+    }
+
+    /// Execute the fuzzed entrypoint `func_name` on `state` and check the invariants
+    fn execute_call(
+        &self,
+        state: &mut StarknetState,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<CallInfo, String> {
+        let call_info = self.execute_fuzzed(state, func_name, data)?;
+        let call = SequenceCall {
+            caller: None,
+            function: func_name.clone(),
+            calldata: data.clone(),
+        };
+        self.check_invariants(state, &vec![call])?;
+        return Ok(call_info);
+    }
+
+    /// Execute the fuzzed entrypoint `func_name` with the caller of the context
+    fn execute_fuzzed(
+        &self,
+        state: &mut StarknetState,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<CallInfo, String> {
+        return self.execute_entry_point(
+            state,
+            &self.address,
+            &self.class_hash,
+            &self.caller_address(),
+            func_name,
+            self.entry_point_type,
+            data,
+        );
+    }
+
+    /// Caller of the calls, taken from the execution context if fuzzed
+    fn caller_address(&self) -> Address {
+        match &self.context {
+            Some(context) => context.caller_address.clone(),
+            None => Address(0000.into()),
+        }
+    }
+
+    /// Execute the input again and get the storage it wrote, including the writes
+    /// made before a failure, decoded to the `@storage_var` names
+    /// Sequences keep the writes of their calls until the first violation
+    pub fn storage_writes(mut self, func_name: &String, data: &Vec<Felt252>) -> Vec<StorageWrite> {
+        let data = match self.take_environment(data) {
+            Ok(data) => data,
+            Err(_) => return Vec::new(),
+        };
+        let mut state = match self.init_state() {
+            Ok(state) => state,
+            Err(_) => return Vec::new(),
+        };
+        // Writes of the constructors of the manifest and of the mock responses
+        let initial = get_storage_writes(&mut state);
+        // Map keys are usually arguments, the caller or values seen during the execution
+        let mut candidates = data.clone();
+        candidates.push(self.caller_address().0);
+        if self.erc20.is_some() {
+            candidates.extend(ERC20_ACCOUNTS.iter().map(|account| Felt252::from(*account)));
+        }
+        if self.access_control.is_some() {
+            candidates.push(Felt252::from(DEPLOYER_ADDRESS));
+            candidates.extend(
+                ACCESS_CONTROL_ACCOUNTS
+                    .iter()
+                    .map(|account| Felt252::from(*account)),
+            );
+        }
+        // The writes of a sequence are kept until its first violation
+        if let Ok(output) = self.execute_input(&mut state, func_name, &data) {
+            candidates.extend(output.retdata);
+            for event in output.events {
+                candidates.extend(event.keys);
+                candidates.extend(event.data);
+            }
+        }
+        let mut decoder = self.storage_decoder.clone();
+        decoder.add_candidates(&candidates);
+        let mut writes: Vec<StorageWrite> = get_storage_writes(&mut state)
+            .into_iter()
+            .filter(|(entry, value)| initial.get(entry) != Some(value))
+            .map(|((address, key), value)| {
+                let key = Felt252::from_bytes_be(&key);
+                StorageWrite {
+                    name: match address == self.address {
+                        true => decoder.name(&key),
+                        false => key.to_string(),
+                    },
+                    address: address.0,
+                    key: key,
+                    value: value,
+                }
+            })
+            .collect();
+        writes.sort_by(|a, b| a.address.cmp(&b.address).then(a.key.cmp(&b.key)));
+        return writes;
+    }
+
+    /// Split the input into the execution context at its beginning, the arguments of
+    /// the function and the responses of the mocks at its end
+    fn split_environment<'a>(
+        &self,
+        data: &'a [Felt252],
+    ) -> Result<(&'a [Felt252], &'a [Felt252], &'a [Felt252]), String> {
+        let context_size = match self.fuzz_context {
+            true => CONTEXT_FIELDS.len(),
+            false => 0,
+        };
+        let mocks_size = self.mocks.as_ref().map_or(0, |mocks| mocks.input_size());
+        if data.len() < context_size + mocks_size {
+            return Err(format!(
+                "Input of {} felts is shorter than its environment of {} felts",
+                data.len(),
+                context_size + mocks_size
+            ));
+        }
+        let (context, data) = data.split_at(context_size);
+        let (args, responses) = data.split_at(data.len() - mocks_size);
+        return Ok((context, args, responses));
+    }
+
+    /// Get the arguments of the function from the input, without its environment
+    pub fn function_args(&self, data: &Vec<Felt252>) -> Result<Vec<Felt252>, String> {
+        let (_, args, _) = self.split_environment(data)?;
+        return Ok(args.to_vec());
+    }
+
+    /// Take the execution context and the responses of the mocks from the input,
+    /// returns the arguments of the function
+    fn take_environment(&mut self, data: &Vec<Felt252>) -> Result<Vec<Felt252>, String> {
+        let (context, args, responses) = self.split_environment(data)?;
+        if self.fuzz_context {
+            let context = ExecutionContext::decode(context);
+            self.address = self.fuzzed_address(&context.contract_address);
+            self.context = Some(context);
+        }
+        self.mock_responses = responses.to_vec();
+        if let Some(diff_runner) = &mut self.diff_runner {
+            diff_runner.address = self.address.clone();
+            diff_runner.context = self.context.clone();
+            diff_runner.mock_responses = self.mock_responses.clone();
+        }
+        return Ok(args.to_vec());
+    }
+
+    /// Read the storage slot `key` of the contract on `state`
+    pub fn read_storage(&self, state: &mut StarknetState, key: &Felt252) -> Felt252 {
+        state
+            .get_storage_at(&(self.address.clone(), key.to_be_bytes()))
+            .unwrap_or(Felt252::zero())
+    }
+
+    /// Get the storage of the contract written by the executions on `state`
+    pub fn get_contract_storage(&self, state: &mut StarknetState) -> HashMap<Felt252, Felt252> {
+        get_storage_writes(state)
+            .into_iter()
+            .filter(|((address, _), _)| address == &self.address)
+            .map(|((_, key), value)| (Felt252::from_bytes_be(&key), value))
+            .collect()
+    }
+
+    /// Execute the calls of a sequence one after the other on `state`
+    /// Calls that fail are reverted and skipped. After each successful call `check`
+    /// gets its index with the states before and after it, then the invariants are
+    /// checked, and the violations are reported with the history of the calls
+    /// `state` keeps the writes of the calls executed until a violation
+    pub fn execute_sequence<F>(
+        &self,
+        state: &mut StarknetState,
+        calls: &Vec<SequenceCall>,
+        mut check: F,
+    ) -> Result<RunnerOutput, String>
+    where
+        F: FnMut(usize, &CallInfo, &mut StarknetState, &mut StarknetState) -> Result<(), String>,
+    {
+        let mut history = Vec::<SequenceCall>::new();
+        let mut output = RunnerOutput::default();
+        for (index, call) in calls.iter().enumerate() {
+            let caller = match &call.caller {
+                Some(caller) => Address(caller.clone()),
+                None => self.caller_address(),
+            };
+            let mut call_state = state.clone();
+            let call_info =
+                match self.execute_from(&mut call_state, &caller, &call.function, &call.calldata) {
+                    Ok(call_info) => call_info,
+                    Err(_) => continue,
+                };
+            history.push(call.clone());
+            let checked = check(index, &call_info, state, &mut call_state);
+            *state = call_state;
+            checked.map_err(|e| format!("{} after calls: {}", e, format_history(&history)))?;
+            self.check_invariants(state, &history)?;
+            output.trace.extend(get_trace(&call_info));
+            output.events.extend(get_events(&call_info));
+            output.messages.extend(get_messages(&call_info));
+            output.retdata = call_info.retdata;
+            output.resources = call_info.execution_resources;
+        }
+        output.storage = self.get_contract_storage(state);
+        return Ok(output);
+    }
+
+    /// Execute the calls encoded in `data` in lockstep with the model
+    /// The model is updated after each successful call and compared with the storage
+    /// Calls that fail are reverted and do not update the model
+    pub fn run_sequence(
+        &self,
+        state: &mut StarknetState,
+        sequence: &Sequence,
+        data: &Vec<Felt252>,
+    ) -> Result<RunnerOutput, String> {
+        let calls = sequence.decode(data);
+        let mut model = (sequence.model)();
+        return self.execute_sequence(state, &calls, |index, call_info, _, state| {
+            model.apply(&Call {
+                function: calls[index].function.clone(),
+                calldata: calls[index].calldata.clone(),
+                retdata: call_info.retdata.clone(),
+            });
+            model
+                .check(&self.get_contract_storage(state))
+                .map_err(|e| format!("Model divergence: {}", e))
+        });
+    }
+
+    /// Execute the input on `state`, as a sequence if one is configured
+    fn execute_input(
+        &self,
+        state: &mut StarknetState,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<RunnerOutput, String> {
+        if let Some(sequence) = &self.sequence {
+            return self.run_sequence(state, sequence, data);
+        }
+        if let Some(erc20) = &self.erc20 {
+            return erc20.run(self, state, data);
+        }
+        if let Some(access_control) = &self.access_control {
+            return access_control.run(self, state, data);
+        }
+        let call_info = self.execute_call(state, func_name, data)?;
+        return Ok(RunnerOutput {
+            trace: get_trace(&call_info),
+            events: get_events(&call_info),
+            messages: get_messages(&call_info),
+            retdata: call_info.retdata,
+            resources: call_info.execution_resources,
+            storage: self.get_contract_storage(state),
+        });
+    }
+}
+
+/// Marker placed before the trace of an internal call, followed by the class tag
+pub const INTERNAL_CALL_MARKER: u32 = u32::MAX;
+
+/// Get the trace of a call and of its internal calls (`call_contract`, `library_call`)
+/// The trace of each internal call is preceded by `(INTERNAL_CALL_MARKER, tag)` where
+/// the tag is taken from its class hash, so identical pcs of different classes
+/// give different coverage
+pub fn get_trace(call_info: &CallInfo) -> Vec<(u32, u32)> {
+    let mut trace = call_info.trace.clone();
+    for internal_call in &call_info.internal_calls {
+        let tag = match internal_call.class_hash {
+            Some(class_hash) => {
+                u32::from_be_bytes([class_hash[0], class_hash[1], class_hash[2], class_hash[3]])
+            }
+            None => 0,
+        };
+        trace.push((INTERNAL_CALL_MARKER, tag));
+        trace.extend(get_trace(internal_call));
+    }
+    return trace;
+}
+
+/// Get the events emitted by a call and its internal calls, in emission order
+pub fn get_events(call_info: &CallInfo) -> Vec<Event> {
+    let mut events = Vec::<(u64, Event)>::new();
+    collect_events(call_info, &mut events);
+    events.sort_by_key(|(order, _)| *order);
+    return events.into_iter().map(|(_, event)| event).collect();
+}
+
+fn collect_events(call_info: &CallInfo, events: &mut Vec<(u64, Event)>) {
+    for event in &call_info.events {
+        events.push((
+            event.order as u64,
+            Event {
+                from_address: call_info.contract_address.0.clone(),
+                keys: event.keys.clone(),
+                data: event.data.clone(),
+            },
+        ));
+    }
+    for internal_call in &call_info.internal_calls {
+        collect_events(internal_call, events);
+    }
+}
+
+/// Get the messages sent to L1 by a call and its internal calls, in emission order
+pub fn get_messages(call_info: &CallInfo) -> Vec<L2ToL1Message> {
+    let mut messages = Vec::<(u64, L2ToL1Message)>::new();
+    collect_messages(call_info, &mut messages);
+    messages.sort_by_key(|(order, _)| *order);
+    return messages.into_iter().map(|(_, message)| message).collect();
+}
+
+fn collect_messages(call_info: &CallInfo, messages: &mut Vec<(u64, L2ToL1Message)>) {
+    for message in &call_info.l2_to_l1_messages {
+        messages.push((
+            message.order as u64,
+            L2ToL1Message {
+                from_address: call_info.contract_address.0.clone(),
+                to_address: message.to_address.0.clone(),
+                payload: message.payload.clone(),
+            },
+        ));
+    }
+    for internal_call in &call_info.internal_calls {
+        collect_messages(internal_call, messages);
+    }
+}
+
+/// Get the storage written by the executions on `state`
+pub fn get_storage_writes(state: &mut StarknetState) -> HashMap<StorageEntry, Felt252> {
+    state.cache_mut().storage_writes_mut().clone()
+}
+
+/// Compare the executions of the same call on two versions of a contract
+fn diff_executions(
+    result: &mut Result<(RunnerOutput, StarknetState), String>,
+    diff_result: &mut Result<(RunnerOutput, StarknetState), String>,
+) -> Result<(), String> {
+    match (result, diff_result) {
+        (Ok((output, state)), Ok((diff_output, diff_state))) => {
+            if output.retdata != diff_output.retdata {
+                return Err(format!(
+                    "Differential mismatch: return values {} != {}",
+                    format_felts(&output.retdata),
+                    format_felts(&diff_output.retdata)
+                ));
+            }
+            let storage = get_storage_writes(state);
+            let diff_storage = get_storage_writes(diff_state);
+            for (entry, value) in storage.iter() {
+                let diff_value = diff_storage.get(entry).cloned().unwrap_or(Felt252::zero());
+                if &diff_value != value {
+                    return Err(format!(
+                        "Differential mismatch: storage {} = {} != {}",
+                        Felt252::from_bytes_be(&entry.1),
+                        value,
+                        diff_value
+                    ));
+                }
+            }
+            for (entry, diff_value) in diff_storage.iter() {
+                if !storage.contains_key(entry) {
+                    return Err(format!(
+                        "Differential mismatch: storage {} = 0 != {}",
+                        Felt252::from_bytes_be(&entry.1),
+                        diff_value
+                    ));
+                }
+            }
+        }
+        (Ok(_), Err(e)) => return Err(diff_failure(e, "second contract")),
+        (Err(e), Ok(_)) => return Err(diff_failure(e, "first contract")),
+        (Err(_), Err(_)) => (),
+    }
+    return Ok(());
+}
+
+/// Format a list of calls as `func(arg1, arg2) -> func(arg1, arg2) by caller`
+pub fn format_history(history: &Vec<SequenceCall>) -> String {
+    history
+        .iter()
+        .map(|call| {
+            let args = call
+                .calldata
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            match &call.caller {
+                Some(caller) => format!("{}({}) by {}", call.function, args, caller),
+                None => format!("{}({})", call.function, args),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
+
+impl Runner for RunnerStarknet {
+    fn runner(
+        mut self,
+        func_name: &String,
+        data: &Vec<Felt252>,
+    ) -> Result<Option<RunnerOutput>, String> {
+        let data = &self.take_environment(data)?;
+        let mut state = self.init_state()?;
+        let mut result = self
+            .execute_input(&mut state, func_name, data)
+            .map(|output| (output, state));
+        if let Some(diff_runner) = &self.diff_runner {
+            let mut diff_state = diff_runner.init_state()?;
+            let mut diff_result = diff_runner
+                .execute_input(&mut diff_state, func_name, data)
+                .map(|output| (output, diff_state));
+            diff_executions(&mut result, &mut diff_result)?;
+        }
+        return result.map(|(output, _)| Some(output));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::manifest::Deployment;
+    use super::super::mock::DEFAULT_MOCK_ARTIFACT;
+    use super::super::snapshot::SnapshotEntry;
+    use super::*;
+    use crate::cli::config::{MockConfig, MockFunction};
+    use crate::json::json_parser::parse_starknet_json;
+    use crate::runner::model::Model;
+    use starknet_rs::execution::{OrderedEvent, OrderedL2ToL1Message};
+    use std::fs;
+    use std::str::FromStr;
+
+    fn runner() -> RunnerStarknet {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let contract_class =
+            ContractClass::from_str(contents.as_str()).expect("could not get contractclass");
+        return RunnerStarknet::new(&contract_class);
+    }
+
+    fn manifest(address: u64, constructor_calldata: Option<Vec<Felt252>>) -> Manifest {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let contract_class =
+            ContractClass::from_str(contents.as_str()).expect("could not get contractclass");
+        return Manifest {
+            deployments: vec![Deployment {
+                artifact: "storage.json".to_string(),
+                address: Address(address.into()),
+                class_hash: [2; 32],
+                contract_class: contract_class,
+                constructor_calldata: constructor_calldata,
+            }],
+        };
+    }
+
+    #[test]
+    fn test_deploy() {
+        let runner = runner().manifest(&manifest(0x2000, None)).deploy().unwrap();
+        // Each execution starts from a copy of the deployed state
+        for _ in 0..2 {
+            let mut state = runner.init_state().unwrap();
+            let call_info = runner
+                .execute_entry_point(
+                    &mut state,
+                    &Address(0x2000.into()),
+                    &[2; 32],
+                    &Address(0.into()),
+                    &"write_and_read".to_string(),
+                    EntryPointType::External,
+                    &vec![Felt252::from(100)],
+                )
+                .unwrap();
+            assert_eq!(call_info.retdata, vec![Felt252::from(100)]);
+        }
+        // The constructors are checked once at deployment
+        let error = runner()
+            .manifest(&manifest(0x2000, Some(Vec::new())))
+            .deploy()
+            .err()
+            .unwrap();
+        assert!(error.starts_with("constructor of storage.json failed"));
+        // The contracts cannot share an address
+        assert_eq!(
+            runner()
+                .manifest(&manifest(CONTRACT_ADDRESS, None))
+                .deploy()
+                .err()
+                .unwrap(),
+            "the fuzzed contract and storage.json are both deployed at 1111"
+        );
+    }
+
+    /// Input with the context of a contract at `address` followed by the argument 100
+    fn context_input(address: u64) -> Vec<Felt252> {
+        let mut data = vec![Felt252::zero(); CONTEXT_FIELDS.len()];
+        data[1] = Felt252::from(address);
+        data.push(Felt252::from(100));
+        return data;
+    }
+
+    #[test]
+    fn test_fuzzed_address() {
+        let decoder = StorageDecoder::from_artifact(
+            &fs::read_to_string("tests/storage.json").expect("Could not read file"),
+        );
+        let key = decoder.key("_counter", &[]).unwrap();
+        let snapshot = Snapshot {
+            entries: vec![SnapshotEntry {
+                address: Some(Felt252::from(CONTRACT_ADDRESS)),
+                key: key.clone(),
+                value: Felt252::from(42),
+            }],
+        };
+        let runner = runner()
+            .fuzz_context(true)
+            .snapshot(&snapshot)
+            .manifest(&manifest(0x2000, None))
+            .deploy()
+            .unwrap();
+        // The contract runs at the fuzzed address with its storage
+        let mut fuzzed = runner.clone();
+        fuzzed.take_environment(&context_input(5)).unwrap();
+        assert_eq!(fuzzed.address, Address(5.into()));
+        let mut state = fuzzed.init_state().unwrap();
+        assert_eq!(fuzzed.read_storage(&mut state, &key), Felt252::from(42));
+        assert!(fuzzed
+            .run(&"write_and_read".to_string(), &vec![Felt252::from(100)])
+            .is_ok());
+        // The zero address and the addresses of the other contracts are not used
+        for address in [0, 0x2000] {
+            let mut fuzzed = runner.clone();
+            fuzzed.take_environment(&context_input(address)).unwrap();
+            assert_eq!(fuzzed.address, Address(CONTRACT_ADDRESS.into()));
+            let mut state = fuzzed.init_state().unwrap();
+            assert_eq!(fuzzed.read_storage(&mut state, &key), Felt252::from(42));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_context() {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let decoder = StorageDecoder::from_artifact(&contents);
+        let key = decoder.key("_counter", &[]).unwrap();
+        // `--state` entries without address are the storage of the fuzzed contract
+        let snapshot = Snapshot::load(&"tests/storage_state.json".to_string(), &decoder);
+        let mut runner = runner()
+            .fuzz_context(true)
+            .snapshot(&snapshot)
+            .storage_decoder(&decoder)
+            .deploy()
+            .unwrap();
+        let data = context_input(5);
+        assert_eq!(runner.take_environment(&data), Ok(vec![Felt252::from(100)]));
+        let mut state = runner.init_state().unwrap();
+        assert_eq!(runner.read_storage(&mut state, &key), Felt252::from(100));
+        // Writing the preloaded value again is not a storage change
+        let name = "write_and_read".to_string();
+        assert!(runner.clone().runner(&name, &data).is_ok());
+        assert_eq!(runner.storage_writes(&name, &data), Vec::new());
+    }
+
+    #[test]
+    fn test_mock_response() {
+        let config = MockConfig {
+            address: "0x3000".to_string(),
+            functions: vec![
+                MockFunction {
+                    name: "get_price".to_string(),
+                    outputs: 2,
+                },
+                MockFunction {
+                    name: "decimals".to_string(),
+                    outputs: 1,
+                },
+            ],
+        };
+        let mocks = Mocks::new(&DEFAULT_MOCK_ARTIFACT.to_string(), &vec![config]);
+        let mut runner = runner().mocks(&Some(mocks.clone())).deploy().unwrap();
+        // The argument of the function is followed by the responses
+        let data = vec![1, 7, 8, 6].into_iter().map(Felt252::from).collect();
+        assert_eq!(runner.take_environment(&data), Ok(vec![Felt252::from(1)]));
+        let mut state = runner.init_state().unwrap();
+        for (function, retdata) in [("get_price", vec![7, 8]), ("decimals", vec![6])] {
+            let call_info = runner
+                .execute_entry_point(
+                    &mut state,
+                    &Address(0x3000.into()),
+                    &mocks.class_hash,
+                    &Address(0.into()),
+                    &function.to_string(),
+                    EntryPointType::External,
+                    &Vec::new(),
+                )
+                .unwrap();
+            let retdata: Vec<Felt252> = retdata.into_iter().map(Felt252::from).collect();
+            assert_eq!(call_info.retdata, retdata);
+        }
+    }
+
+    #[test]
+    fn test_get_trace() {
+        let mut class_hash = [0; 32];
+        class_hash[3] = 5;
+        let library_call = CallInfo {
+            trace: vec![(5, 6)],
+            ..Default::default()
+        };
+        let internal_call = CallInfo {
+            class_hash: Some(class_hash),
+            trace: vec![(3, 4)],
+            internal_calls: vec![library_call],
+            ..Default::default()
+        };
+        let call_info = CallInfo {
+            trace: vec![(1, 2)],
+            internal_calls: vec![internal_call],
+            ..Default::default()
+        };
+        // Each internal call is tagged with its class hash, 0 if unknown
+        assert_eq!(
+            get_trace(&call_info),
+            vec![
+                (1, 2),
+                (INTERNAL_CALL_MARKER, 5),
+                (3, 4),
+                (INTERNAL_CALL_MARKER, 0),
+                (5, 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entry_point() {
+        let name = "write_and_read".to_string();
+        let data = vec![Felt252::from(100)];
+        assert!(runner().run(&name, &data).is_ok());
+        // The function is only an external entrypoint of the contract
+        for decorator in ["l1_handler", "constructor"] {
+            let runner = runner().entry_point(&vec![decorator.to_string()]);
+            assert!(runner.run(&name, &data).is_err());
+        }
+    }
+
+    #[test]
+    fn test_get_events() {
+        let felts = |values: &[u64]| -> Vec<Felt252> {
+            values.iter().map(|value| Felt252::from(*value)).collect()
+        };
+        let internal_call = CallInfo {
+            contract_address: Address(2222.into()),
+            events: vec![OrderedEvent::new(1, felts(&[2]), felts(&[20]))],
+            l2_to_l1_messages: vec![OrderedL2ToL1Message::new(
+                0,
+                Address(42.into()),
+                felts(&[7]),
+            )],
+            ..Default::default()
+        };
+        let call_info = CallInfo {
+            contract_address: Address(1111.into()),
+            events: vec![
+                OrderedEvent::new(0, felts(&[1]), felts(&[10])),
+                OrderedEvent::new(2, felts(&[3]), felts(&[30])),
+            ],
+            l2_to_l1_messages: vec![OrderedL2ToL1Message::new(
+                1,
+                Address(42.into()),
+                felts(&[8]),
+            )],
+            internal_calls: vec![internal_call],
+            ..Default::default()
+        };
+        // The events of the internal calls are merged in emission order
+        let events = get_events(&call_info);
+        let keys: Vec<Vec<Felt252>> = events.iter().map(|event| event.keys.clone()).collect();
+        assert_eq!(keys, vec![felts(&[1]), felts(&[2]), felts(&[3])]);
+        assert_eq!(events[1].from_address, Felt252::from(2222));
+        let messages = get_messages(&call_info);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].from_address, Felt252::from(2222));
+        assert_eq!(messages[0].payload, felts(&[7]));
+        assert_eq!(messages[1].from_address, Felt252::from(1111));
+    }
+
+    struct Diverging;
+
+    impl Model for Diverging {
+        fn functions(&self) -> Vec<String> {
+            vec!["write_and_read".to_string()]
+        }
+        fn apply(&mut self, _call: &Call) {}
+        fn check(&self, _storage: &HashMap<Felt252, Felt252>) -> Result<(), String> {
+            Err("diverged".to_string())
+        }
+    }
+
+    #[test]
+    fn test_sequence_storage_writes() {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let function = parse_starknet_json(&contents, &"write_and_read".to_string()).unwrap();
+        let sequence = Sequence {
+            functions: vec![function],
+            length: 2,
+            model: Arc::new(|| Box::new(Diverging)),
+        };
+        let runner = runner()
+            .storage_decoder(&StorageDecoder::from_artifact(&contents))
+            .sequence(&sequence);
+        let name = "write_and_read".to_string();
+        let data = vec![0, 100, 0, 100]
+            .into_iter()
+            .map(Felt252::from)
+            .collect();
+        assert!(runner.clone().runner(&name, &data).is_err());
+        // The writes of the call that diverged are kept
+        let writes = runner.storage_writes(&name, &data);
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].name, "_counter");
+        assert_eq!(writes[0].value, Felt252::from(100));
+    }
+
+    #[test]
+    fn test_sequence_mode() {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let function = parse_starknet_json(&contents, &"write_and_read".to_string()).unwrap();
+        assert_eq!(runner().sequence_mode(), None);
+        let sequence = Sequence {
+            functions: vec![function.clone()],
+            length: 2,
+            model: Arc::new(|| Box::new(Diverging)),
+        };
+        assert_eq!(
+            runner().sequence(&sequence).sequence_mode(),
+            Some("registered model")
+        );
+        let access_control =
+            AccessControl::new(&vec![function], &StorageDecoder::default(), &Vec::new(), 2);
+        assert_eq!(
+            runner().access_control(&access_control).sequence_mode(),
+            Some("access-control detector")
+        );
+    }
+
+    #[test]
+    fn test_decode_context() {
+        let data: Vec<Felt252> = (1..=CONTEXT_FIELDS.len()).map(Felt252::from).collect();
+        let context = ExecutionContext::decode(&data);
+        assert_eq!(context.caller_address, Address(1.into()));
+        assert_eq!(context.contract_address, Address(2.into()));
+        assert_eq!(context.block_number, 3);
+        assert_eq!(context.block_timestamp, 4);
+        assert_eq!(context.sequencer_address, Address(5.into()));
+        assert_eq!(context.nonce, Felt252::from(6));
+        assert_eq!(context.version, Felt252::from(7));
+        assert_eq!(context.signature, vec![Felt252::from(8), Felt252::from(9)]);
+        // Block values are truncated to their 64 low bits
+        let mut data = data;
+        data[3] = Felt252::from(u64::MAX) + Felt252::from(2);
+        assert_eq!(ExecutionContext::decode(&data).block_timestamp, 1);
+    }
+
+    #[test]
+    fn test_function_args() {
+        let runner = runner().fuzz_context(true);
+        let mut data: Vec<Felt252> = (0..CONTEXT_FIELDS.len()).map(Felt252::from).collect();
+        data.push(Felt252::from(42));
+        assert_eq!(runner.function_args(&data), Ok(vec![Felt252::from(42)]));
+        // A short input is an error instead of a panic
+        let data = vec![Felt252::from(1); 3];
+        assert!(runner.function_args(&data).is_err());
+        assert!(runner.runner(&"write_and_read".to_string(), &data).is_err());
+    }
+
+    #[test]
+    fn test_format_history() {
+        let history = vec![
+            SequenceCall {
+                caller: None,
+                function: "initialize".to_string(),
+                calldata: vec![Felt252::from(4096)],
+            },
+            SequenceCall {
+                caller: Some(Felt252::from(8194)),
+                function: "set_fee".to_string(),
+                calldata: vec![Felt252::from(5), Felt252::from(6)],
+            },
+        ];
+        assert_eq!(
+            format_history(&history),
+            "initialize(4096) -> set_fee(5, 6) by 8194"
+        );
+    }
 }
@@ -0,0 +1,188 @@
+use felt::Felt252;
+use starknet_rs::utils::get_storage_var_address;
+use std::collections::HashMap;
+
+use crate::json::json_parser::{get_storage_vars, parse_felt};
+
+/// Maximum number of key felts of the maps decoded from the candidates
+/// A map with `n` key felts is checked against `candidates^n` addresses
+pub const MAX_DECODED_KEYS: u64 = 2;
+
+/// Reverse lookup of the storage keys of a contract from its `@storage_var` declarations
+///
+/// The address of a storage variable is the sn_keccak of its name, and the address
+/// of a map entry is the pedersen hash of this base address with the keys. Map keys
+/// can not be inverted, so they are found among candidate values seen during the
+/// execution (calldata, callers, return values, ...) registered with `add_candidates`.
+/// A value of several felts (`Uint256`, structs) takes the slots following its address,
+/// named with their offset.
+/// ```ignore
+/// let mut decoder = StorageDecoder::from_artifact(&contents);
+/// decoder.add_candidates(&calldata);
+/// decoder.name(&key); // "balances(4097)"
+/// decoder.name(&(key + 1u32)); // "balances(4097)+1", high word of a Uint256
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StorageDecoder {
+    /// Storage variables of the contract as (name, number of keys, number of felts of the value)
+    pub storage_vars: Vec<(String, u64, u64)>,
+    /// Known storage keys and their names
+    names: HashMap<Felt252, String>,
+}
+
+impl StorageDecoder {
+    /// Create the decoder, the base address of every storage variable is known
+    pub fn new(storage_vars: &Vec<(String, u64, u64)>) -> Self {
+        let mut decoder = StorageDecoder {
+            storage_vars: storage_vars.clone(),
+            names: HashMap::new(),
+        };
+        for (name, _, size) in storage_vars {
+            if let Ok(key) = get_storage_var_address(name, &[]) {
+                decoder.insert(key, name.clone(), *size);
+            }
+        }
+        return decoder;
+    }
+
+    /// Name the `size` slots of a value stored at `key`
+    fn insert(&mut self, key: Felt252, name: String, size: u64) {
+        for offset in 1..size {
+            self.names
+                .entry(key.clone() + Felt252::from(offset))
+                .or_insert(format!("{}+{}", name, offset));
+        }
+        self.names.entry(key).or_insert(name);
+    }
+
+    /// Create the decoder from the identifiers of a Starknet artifact
+    pub fn from_artifact(data: &String) -> Self {
+        return StorageDecoder::new(&get_storage_vars(data));
+    }
+
+    /// Compute the addresses of the map entries whose keys are among `candidates`
+    pub fn add_candidates(&mut self, candidates: &Vec<Felt252>) {
+        let mut candidates = candidates.clone();
+        candidates.sort();
+        candidates.dedup();
+        for (name, keys, size) in self.storage_vars.clone() {
+            if keys == 0 || keys > MAX_DECODED_KEYS {
+                continue;
+            }
+            let mut args_list: Vec<Vec<Felt252>> = vec![Vec::new()];
+            for _ in 0..keys {
+                args_list = args_list
+                    .iter()
+                    .flat_map(|args| {
+                        candidates.iter().map(move |candidate| {
+                            let mut args = args.clone();
+                            args.push(candidate.clone());
+                            args
+                        })
+                    })
+                    .collect();
+            }
+            for args in args_list {
+                if let Ok(key) = get_storage_var_address(&name, &args) {
+                    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                    self.insert(key, format!("{}({})", name, args.join(", ")), size);
+                }
+            }
+        }
+    }
+
+    /// Name of a storage key with its map keys, `None` if unknown
+    pub fn lookup(&self, key: &Felt252) -> Option<String> {
+        self.names.get(key).cloned()
+    }
+
+    /// Name of a storage key with its map keys, the raw key if unknown
+    pub fn name(&self, key: &Felt252) -> String {
+        self.lookup(key).unwrap_or(key.to_string())
+    }
+
+    /// Storage key of the storage variable `name` with the map keys `args`
+    pub fn key(&self, name: &str, args: &[Felt252]) -> Result<Felt252, String> {
+        match self.storage_vars.iter().find(|(var, _, _)| var == name) {
+            Some((_, keys, _)) if *keys as usize != args.len() => {
+                return Err(format!(
+                    "storage variable {} has {} keys, got {}",
+                    name,
+                    keys,
+                    args.len()
+                ))
+            }
+            Some(_) => (),
+            None if self.storage_vars.is_empty() => (),
+            None => return Err(format!("unknown storage variable {}", name)),
+        }
+        return get_storage_var_address(name, args)
+            .map_err(|_| format!("could not compute the address of {}", name));
+    }
+}
+
+/// Get the storage key of `key`, either a raw felt or a `@storage_var` name with its arguments
+/// and an optional `+offset` of the slot in the value
+/// The storage variables of `decoder` are checked if it has any
+pub fn parse_storage_key(key: &str, decoder: &StorageDecoder) -> Result<Felt252, String> {
+    if let Ok(value) = parse_felt(key) {
+        return Ok(value);
+    }
+    // The slots following the address of a value are named with their offset
+    if let Some((base, offset)) = key.rsplit_once('+') {
+        if let Ok(offset) = offset.trim().parse::<u64>() {
+            return Ok(parse_storage_key(base.trim(), decoder)? + Felt252::from(offset));
+        }
+    }
+    let (name, args) = match key.find('(') {
+        Some(index) if key.ends_with(')') => (&key[..index], &key[index + 1..key.len() - 1]),
+        Some(_) => return Err(format!("missing ')' in key {}", key)),
+        None => (key, ""),
+    };
+    let mut values = Vec::<Felt252>::new();
+    for arg in args
+        .split(',')
+        .map(|arg| arg.trim())
+        .filter(|arg| !arg.is_empty())
+    {
+        match parse_felt(arg) {
+            Ok(value) => values.push(value),
+            Err(e) => return Err(format!("{} in key {}", e, key)),
+        }
+    }
+    return decoder.key(name.trim(), &values);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_storage_json() {
+        let contents = fs::read_to_string("tests/storage.json").expect("Could not read file");
+        let decoder = StorageDecoder::from_artifact(&contents);
+        let key = decoder.key("_counter", &[]).unwrap();
+        assert_eq!(decoder.name(&key), "_counter");
+        assert_eq!(parse_storage_key("_counter", &decoder), Ok(key.clone()));
+        assert_eq!(parse_storage_key(&key.to_string(), &decoder), Ok(key));
+        assert!(parse_storage_key("_counter(1)", &decoder).is_err());
+        assert!(parse_storage_key("balances", &decoder).is_err());
+    }
+
+    #[test]
+    fn test_member_slots() {
+        // balances(account) -> Uint256
+        let mut decoder = StorageDecoder::new(&vec![("balances".to_string(), 1, 2)]);
+        decoder.add_candidates(&vec![Felt252::from(4097)]);
+        let key = decoder.key("balances", &[Felt252::from(4097)]).unwrap();
+        let high = key.clone() + Felt252::from(1);
+        assert_eq!(decoder.name(&key), "balances(4097)");
+        assert_eq!(decoder.name(&high), "balances(4097)+1");
+        assert_eq!(decoder.lookup(&(key.clone() + Felt252::from(2))), None);
+        // The names are parsed back to their keys
+        for slot in [key, high] {
+            assert_eq!(parse_storage_key(&decoder.name(&slot), &decoder), Ok(slot));
+        }
+    }
+}